@@ -1,4 +1,8 @@
+use crate::constants::CAMERA_LOOK_AHEAD;
+use crate::enemies::HazardEffect;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 
 #[derive(Component)]
 pub struct Player {
@@ -15,12 +19,37 @@ impl Default for Player {
     }
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
 pub enum WeaponType {
     Trail,
     Wave,
 }
 
+impl WeaponType {
+    pub fn display_name(&self) -> &'static str {
+        match self {
+            WeaponType::Trail => "Trail",
+            WeaponType::Wave => "Wave",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            WeaponType::Trail => WeaponType::Wave,
+            WeaponType::Wave => WeaponType::Trail,
+        }
+    }
+}
+
+/// What dealt the killing blow to an enemy.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum KillSource {
+    Trail,
+    Wave,
+    /// The enemy ran into the player.
+    Contact,
+}
+
 #[derive(Component)]
 pub struct PlayerVelocity {
     pub current: Vec2,
@@ -36,9 +65,62 @@ impl Default for PlayerVelocity {
 
 #[derive(Component)]
 pub struct Enemy {
+    /// Index into `EnemyCatalog::archetypes`.
+    pub archetype: usize,
     pub speed: f32,
 }
 
+/// Per-enemy state for the steering behaviours that need it.
+#[derive(Component, Default)]
+pub struct SteeringState {
+    pub charge: ChargePhase,
+    /// Seconds left in the current charge phase.
+    pub timer: f32,
+    /// Direction locked in when a dash starts.
+    pub heading: Vec2,
+    /// +1 or -1; which way orbiters and shooters circle.
+    pub circle_direction: f32,
+}
+
+/// A boss's phase and attack rotation; movement for bosses is driven from
+/// here rather than the archetype's steering behaviour.
+#[derive(Component)]
+pub struct Boss {
+    pub name: String,
+    pub max_health: f32,
+    pub phase: usize,
+    /// Seconds until the next attack starts.
+    pub cooldown: f32,
+    pub next_pattern: usize,
+    pub action: BossAction,
+    /// Seconds before touching the player can hurt them again.
+    pub contact_cooldown: f32,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum BossAction {
+    #[default]
+    Stalk,
+    WindUp {
+        remaining: f32,
+        dash_velocity: Vec2,
+        dash_duration: f32,
+    },
+    Dash {
+        remaining: f32,
+        velocity: Vec2,
+    },
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ChargePhase {
+    #[default]
+    Approach,
+    WindUp,
+    Dash,
+    Recover,
+}
+
 #[derive(Component)]
 pub struct EnemyHealth {
     pub current: f32,
@@ -57,12 +139,51 @@ impl Default for EnemyVelocity {
     }
 }
 
-#[derive(Component)]
-pub struct TrailSegment {
+/// One sample of the player's path. `damage` is fixed when it's laid down.
+#[derive(Clone, Copy, Debug)]
+pub struct TrailPoint {
+    pub position: Vec2,
     pub remaining: f32,
     pub damage: f32,
 }
 
+/// The player's recent path, oldest first: drawn as the thread and what
+/// trail hits are tested against.
+#[derive(Component, Default)]
+pub struct TrailHistory {
+    pub points: VecDeque<TrailPoint>,
+}
+
+/// Seconds before the trail can damage this enemy again.
+#[derive(Component, Default)]
+pub struct TrailHitCooldown {
+    pub remaining: f32,
+}
+
+/// The mesh entity the thread is drawn with.
+#[derive(Component)]
+pub struct TrailRibbon;
+
+/// Translation of an entity moved in `FixedUpdate`, kept apart from its
+/// `Transform` so rendering can blend between the last two steps. Anything
+/// that moves the transform directly is taken as a teleport.
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Interpolated {
+    pub previous: Vec3,
+    pub current: Vec3,
+    /// What interpolation last wrote to the transform.
+    pub rendered: Vec3,
+}
+
+impl Interpolated {
+    /// Jumps straight to `translation` with nothing to blend from.
+    pub fn snap(&mut self, translation: Vec3) {
+        self.previous = translation;
+        self.current = translation;
+        self.rendered = translation;
+    }
+}
+
 #[derive(Component)]
 pub struct Knockback {
     pub velocity: Vec2,
@@ -82,6 +203,42 @@ pub struct WaveProjectile {
     pub age: f32,
     pub lifetime: f32,
     pub damage: u32,
+    /// Extra enemies the projectile can pass through before it is spent.
+    pub pierce: u32,
+    /// Enemies already struck, so a piercing shot never hits the same one twice.
+    pub hits: Vec<Entity>,
+}
+
+/// A shot fired by an enemy; spent on the first hit.
+#[derive(Component)]
+pub struct EnemyProjectile {
+    pub velocity: Vec2,
+    pub age: f32,
+    pub lifetime: f32,
+    pub damage: u32,
+}
+
+/// A zone on the ground that affects the player while they stand in it.
+#[derive(Component)]
+pub struct Hazard {
+    pub effect: HazardEffect,
+    /// Seconds before the zone starts working, so it can be seen and avoided.
+    pub arming: f32,
+    pub remaining: f32,
+    /// Seconds until a damage zone can hurt again.
+    pub tick: f32,
+}
+
+impl Hazard {
+    pub fn is_armed(&self) -> bool {
+        self.arming <= 0.0
+    }
+}
+
+/// Seconds until an enemy can use its archetype's `EnemyAttack` again.
+#[derive(Component, Default)]
+pub struct AttackCooldown {
+    pub remaining: f32,
 }
 
 #[derive(Component)]
@@ -96,7 +253,8 @@ pub struct PowerUp {
     pub kind: PowerUpKind,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
 pub enum PowerUpKind {
     Currency,
     Health,
@@ -128,9 +286,30 @@ impl Default for ScreenShake {
 #[derive(Component)]
 pub struct MainCamera;
 
+/// Where the main camera is looking, before screen shake. The shake offset is
+/// layered on top when the transform is written, so it never drags the follow.
+#[derive(Component)]
+pub struct CameraRig {
+    pub focus: Vec2,
+    /// Seconds of player velocity to lead by; 0 disables look-ahead.
+    pub look_ahead: f32,
+}
+
+impl Default for CameraRig {
+    fn default() -> Self {
+        Self {
+            focus: Vec2::ZERO,
+            look_ahead: CAMERA_LOOK_AHEAD,
+        }
+    }
+}
+
 #[derive(Component)]
 pub struct Background;
 
+#[derive(Component)]
+pub struct ArenaEdge;
+
 #[derive(Component)]
 pub struct HudRoot;
 
@@ -143,6 +322,24 @@ pub struct HudHealth;
 #[derive(Component)]
 pub struct HudCombo;
 
+#[derive(Component)]
+pub struct HudCurrency;
+
+#[derive(Component)]
+pub struct HudWeapon;
+
+#[derive(Component)]
+pub struct HudWave;
+
+#[derive(Component)]
+pub struct HudBossBar;
+
+#[derive(Component)]
+pub struct HudBossName;
+
+#[derive(Component)]
+pub struct HudBossFill;
+
 #[derive(Component)]
 pub struct HudStatus;
 
@@ -155,6 +352,15 @@ pub struct HudHealthBar;
 #[derive(Component)]
 pub struct ShopRoot;
 
+#[derive(Component)]
+pub struct TitleOverlay;
+
+#[derive(Component)]
+pub struct PauseOverlay;
+
+#[derive(Component)]
+pub struct GameOverOverlay;
+
 #[derive(Component)]
 pub struct ShopButton;
 
@@ -1,26 +1,28 @@
 use bevy::prelude::*;
 
 pub const PLAYER_SPEED: f32 = 950.0;
-pub const PLAYER_RADIUS: f32 = 14.0;
+pub const PLAYER_SIZE: f32 = 48.0;
+pub const PLAYER_RADIUS: f32 = 18.0;
 pub const PLAYER_MAX_HEALTH: u32 = 4;
 pub const PLAYER_COLLISION_DAMAGE: u32 = 1;
 pub const TRAIL_LIFETIME: f32 = 2.6;
 pub const TRAIL_SPAWN_INTERVAL: f32 = 0.028;
-pub const TRAIL_HIT_RADIUS: f32 = 16.0;
+pub const TRAIL_WIDTH: f32 = 18.0;
+pub const TRAIL_DAMAGE_INTERVAL: f32 = 0.2;
 pub const ENEMY_BASE_SPEED: f32 = 180.0;
 pub const ENEMY_SPEED_INCREMENT: f32 = 8.0;
-pub const ENEMY_SPAWN_INTERVAL_START: f32 = 2.0;
-pub const ENEMY_SPAWN_ACCELERATION: f32 = 0.92;
 pub const ENEMY_SIZE: Vec2 = Vec2::new(36.0, 36.0);
 pub const COMBO_WINDOW: f32 = 1.0;
 pub const COMBO_MULTIPLIER_STEP: f32 = 0.5;
+pub const COMBO_MAX_MULTIPLIER: f32 = 4.0;
+pub const COMBO_MILESTONES: [u32; 4] = [5, 10, 20, 40];
 pub const BASE_SCORE: u32 = 10;
-pub const ARENA_BOUNDS: Vec2 = Vec2::new(1024.0, 768.0);
 pub const ENEMY_BASE_HEALTH: u32 = 3;
 pub const TRAIL_BASE_DAMAGE: u32 = 3;
 pub const SHIELD_DURATION: f32 = 4.0;
 
 pub const POWER_UP_LIFETIME: f32 = 12.0;
+pub const POWER_UP_SIZE: f32 = 24.0;
 pub const POWER_UP_DROP_CHANCE: f32 = 0.15;
 pub const POWER_UP_HEART_WEIGHT: f32 = 0.35;
 pub const POWER_UP_SHIELD_WEIGHT: f32 = 0.25;
@@ -29,16 +31,50 @@ pub const POWER_UP_ACCURACY_WEIGHT: f32 = 0.15;
 pub const POWER_UP_WAVEBLAST_WEIGHT: f32 = 0.10;
 
 pub const SCREEN_SHAKE_DECAY: f32 = 3.0;
+pub const PARTICLE_BUDGET: usize = 400;
+pub const HIT_PARTICLE_COUNT: u32 = 3;
+pub const DEATH_PARTICLE_COUNT: u32 = 10;
+pub const PICKUP_PARTICLE_COUNT: u32 = 12;
+pub const SHIELD_BREAK_PARTICLE_COUNT: u32 = 18;
+pub const PLAYER_HIT_PARTICLE_COUNT: u32 = 14;
+pub const HAZARD_PARTICLE_RATE: f32 = 6.0;
 pub const ENEMY_KNOCKBACK: f32 = 250.0;
 pub const PLAYER_KNOCKBACK_STRENGTH: f32 = 200.0;
 pub const HIT_FREEZE_DURATION: f32 = 0.04;
 pub const PLAYER_ACCELERATION: f32 = 0.12;
 pub const PLAYER_DECELERATION: f32 = 0.25;
 pub const ENEMY_TURN_SPEED: f32 = 0.18;
+/// Share of knockback velocity kept from one step to the next.
+pub const PLAYER_KNOCKBACK_DAMPING: f32 = 0.90;
+pub const ENEMY_KNOCKBACK_DAMPING: f32 = 0.92;
+
+/// Gameplay steps per second. The per-step lerp and damping factors above
+/// were tuned at this rate; `util::smoothing` and `util::decay` rescale them
+/// for any other interval.
+pub const SIMULATION_HZ: f64 = 60.0;
 
 pub const CAMERA_SMOOTHING: f32 = 0.30;
+pub const CAMERA_LOOK_AHEAD: f32 = 0.2;
+pub const CAMERA_LOOK_AHEAD_MAX: f32 = 120.0;
 pub const ARENA_SIZE: f32 = 5000.0;
+pub const ARENA_EDGE_THICKNESS: f32 = 8.0;
 pub const ENEMY_SPAWN_DISTANCE: f32 = 600.0;
+/// Closest to the player an enemy may appear once walls push it inward.
+pub const ENEMY_SPAWN_MIN_DISTANCE: f32 = 300.0;
+pub const ENEMY_CAP: usize = 60;
+
+pub const DIRECTOR_FIRST_LULL: f32 = 1.5;
+pub const DIRECTOR_LULL: f32 = 4.0;
+pub const DIRECTOR_PEAK_LULL: f32 = 8.0;
+pub const DIRECTOR_PEAK_EVERY: u32 = 5;
+pub const DIRECTOR_GROUP_SPACING: f32 = 2.5;
+
+pub const BOSS_ESCORT_GROUPS: usize = 2;
+pub const BOSS_CONTACT_DAMAGE: u32 = 2;
+pub const BOSS_CONTACT_COOLDOWN: f32 = 1.0;
+
+pub const HAZARD_ARM_TIME: f32 = 0.6;
+pub const ENEMY_PROJECTILE_KNOCKBACK: f32 = 120.0;
 
 pub const WAVE_COOLDOWN: f32 = 0.35;
 pub const WAVE_PROJECTILE_COUNT: u32 = 5;
@@ -46,6 +82,8 @@ pub const WAVE_SPREAD_ANGLE: f32 = 0.4;
 pub const WAVE_SPEED: f32 = 800.0;
 pub const WAVE_LIFETIME: f32 = 1.5;
 pub const WAVE_DAMAGE: u32 = 2;
+pub const WAVE_PROJECTILE_SIZE: Vec2 = Vec2::new(32.0, 16.0);
+pub const WAVE_PIERCE: u32 = 1;
 
 pub const PLAYER_START_HEALTH: u32 = 4;
 pub const PLAYER_BOOSTED_SPEED: f32 = 950.0;
@@ -56,3 +94,7 @@ pub const SHIELD_TACTICAL_DURATION: f32 = 4.0;
 pub const POWER_UP_RARE_CHANCE: f32 = 0.15;
 
 pub const TOUCH_DRAG_DEADZONE: f32 = 12.0;
+
+pub const RUN_HISTORY_LIMIT: usize = 20;
+
+pub const SAVE_FLUSH_INTERVAL: f32 = 2.0;
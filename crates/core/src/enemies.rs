@@ -0,0 +1,624 @@
+use crate::components::PowerUpKind;
+use crate::constants::{ENEMY_BASE_HEALTH, ENEMY_BASE_SPEED};
+use crate::hitbox::Hitbox;
+use crate::waves::WavePlan;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// How an archetype moves. Parameters come straight from the data file.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SteeringBehavior {
+    /// Homes straight at the player.
+    Chaser,
+    /// Closes to `trigger_range`, stops to wind up, then dashes along the
+    /// line it locked onto before recovering.
+    Charger {
+        trigger_range: f32,
+        windup: f32,
+        dash_speed: f32,
+        dash_duration: f32,
+        recover: f32,
+    },
+    /// Circles the player at roughly `radius`.
+    Orbiter { radius: f32 },
+    /// Holds position around `range` from the player, strafing while in band.
+    Shooter { range: f32 },
+}
+
+/// Health at spawn: `base + per_minute * minutes_into_run`.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HealthCurve {
+    pub base: f32,
+    #[serde(default)]
+    pub per_minute: f32,
+}
+
+impl HealthCurve {
+    pub fn at(&self, elapsed: f32) -> f32 {
+        self.base + self.per_minute * elapsed / 60.0
+    }
+}
+
+/// Enemies released when this archetype dies.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SplitSpec {
+    pub archetype: String,
+    pub count: u32,
+}
+
+/// A shot fired at the player.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProjectileSpec {
+    pub speed: f32,
+    pub damage: u32,
+    pub radius: f32,
+    /// Seconds before the shot fizzles out.
+    pub lifetime: f32,
+}
+
+/// What a hazard does to the player standing in it.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum HazardEffect {
+    /// Scales the player's speed by `factor`.
+    Slow { factor: f32 },
+    /// Deals `damage` every `interval` seconds.
+    Damage { damage: u32, interval: f32 },
+}
+
+/// A zone left on the ground for `duration` seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HazardSpec {
+    pub radius: f32,
+    pub duration: f32,
+    pub effect: HazardEffect,
+}
+
+/// A ranged attack a regular archetype uses every `cooldown` seconds.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum EnemyAttack {
+    /// Fires `count` shots fanned across `spread` radians at the player once
+    /// they're within `range`.
+    Volley {
+        cooldown: f32,
+        range: f32,
+        shot: ProjectileSpec,
+        #[serde(default = "default_count")]
+        count: u32,
+        #[serde(default)]
+        spread: f32,
+    },
+    /// Leaves a hazard where the enemy stands.
+    Trail { cooldown: f32, hazard: HazardSpec },
+}
+
+impl EnemyAttack {
+    pub fn cooldown(&self) -> f32 {
+        match self {
+            Self::Volley { cooldown, .. } | Self::Trail { cooldown, .. } => *cooldown,
+        }
+    }
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+/// One attack in a boss phase's rotation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum BossPattern {
+    /// Locks onto the player's position, telegraphs for `windup` seconds,
+    /// then charges along that line.
+    Dash {
+        windup: f32,
+        speed: f32,
+        duration: f32,
+    },
+    /// Releases `count` enemies of `archetype` in a ring around the boss.
+    Summon { archetype: String, count: u32 },
+    /// Fires `count` shots fanned across `spread` radians at the player.
+    Volley {
+        shot: ProjectileSpec,
+        count: u32,
+        #[serde(default)]
+        spread: f32,
+    },
+    /// Drops `count` hazards within `scatter` of the player's position.
+    Hazards {
+        hazard: HazardSpec,
+        count: u32,
+        #[serde(default)]
+        scatter: f32,
+    },
+}
+
+/// Behaviour while the boss's health fraction is at or below `below`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossPhase {
+    pub below: f32,
+    pub speed: f32,
+    /// Seconds between attacks.
+    pub cooldown: f32,
+    pub patterns: Vec<BossPattern>,
+}
+
+/// Dropped every time the boss dies, on top of the usual kill rewards.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct BossReward {
+    #[serde(default)]
+    pub currency: u32,
+    #[serde(default)]
+    pub powerups: Vec<PowerUpKind>,
+}
+
+/// Turns an archetype into a boss. Phases are ordered from full health down.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossSpec {
+    pub name: String,
+    pub phases: Vec<BossPhase>,
+    #[serde(default)]
+    pub reward: BossReward,
+}
+
+impl BossSpec {
+    /// Index of the phase for a health fraction in `0..=1`.
+    pub fn phase_at(&self, fraction: f32) -> usize {
+        self.phases
+            .iter()
+            .rposition(|phase| fraction <= phase.below)
+            .unwrap_or(0)
+    }
+}
+
+/// Makes wave `wave` a boss wave, and every `repeat_every` waves after it.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossMilestone {
+    pub wave: u32,
+    pub archetype: String,
+    #[serde(default)]
+    pub repeat_every: Option<u32>,
+}
+
+impl BossMilestone {
+    pub fn matches(&self, wave: u32) -> bool {
+        match self.repeat_every {
+            _ if wave == self.wave => true,
+            Some(every) if every > 0 && wave > self.wave => {
+                (wave - self.wave).is_multiple_of(every)
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnemyArchetype {
+    pub id: String,
+    /// Asset path of the sprite.
+    pub sprite: String,
+    pub size: [f32; 2],
+    #[serde(default = "default_tint")]
+    pub tint: [f32; 3],
+    /// Defaults to a circle inscribed in `size`.
+    #[serde(default)]
+    pub hitbox: Option<Hitbox>,
+    pub health: HealthCurve,
+    pub speed: f32,
+    #[serde(default)]
+    pub speed_per_minute: f32,
+    pub behavior: SteeringBehavior,
+    #[serde(default)]
+    pub split: Option<SplitSpec>,
+    #[serde(default)]
+    pub attack: Option<EnemyAttack>,
+    #[serde(default)]
+    pub boss: Option<BossSpec>,
+}
+
+fn default_tint() -> [f32; 3] {
+    [1.0, 1.0, 1.0]
+}
+
+impl EnemyArchetype {
+    pub fn hitbox(&self) -> Hitbox {
+        self.hitbox.unwrap_or(Hitbox::Circle {
+            radius: self.size[0].min(self.size[1]) * 0.5,
+        })
+    }
+
+    pub fn speed_at(&self, elapsed: f32) -> f32 {
+        self.speed + self.speed_per_minute * elapsed / 60.0
+    }
+}
+
+/// Spawn weights from `at` seconds into a run. Weights are blended linearly
+/// between neighbouring stages; ids missing from a stage weigh 0 there.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnStage {
+    pub at: f32,
+    pub weights: HashMap<String, f32>,
+}
+
+/// Every enemy the game knows about, the table generated waves draw from and
+/// any scripted opening waves. Loaded from `data/enemies.json`.
+#[derive(Asset, Resource, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnemyCatalog {
+    pub archetypes: Vec<EnemyArchetype>,
+    pub spawn_table: Vec<SpawnStage>,
+    /// Played in order before the director starts generating waves.
+    #[serde(default)]
+    pub waves: Vec<WavePlan>,
+    #[serde(default)]
+    pub boss_waves: Vec<BossMilestone>,
+}
+
+impl Default for EnemyCatalog {
+    /// The single homing enemy the game shipped with, used when the data file
+    /// can't be loaded.
+    fn default() -> Self {
+        Self {
+            archetypes: vec![EnemyArchetype {
+                id: "chaser".into(),
+                sprite: "sprites/enemy_basic.png".into(),
+                size: [48.0, 44.0],
+                tint: default_tint(),
+                hitbox: None,
+                health: HealthCurve {
+                    base: ENEMY_BASE_HEALTH as f32,
+                    per_minute: 1.0,
+                },
+                speed: ENEMY_BASE_SPEED,
+                speed_per_minute: 20.0,
+                behavior: SteeringBehavior::Chaser,
+                split: None,
+                attack: None,
+                boss: None,
+            }],
+            spawn_table: vec![SpawnStage {
+                at: 0.0,
+                weights: HashMap::from([("chaser".into(), 1.0)]),
+            }],
+            waves: Vec::new(),
+            boss_waves: Vec::new(),
+        }
+    }
+}
+
+impl EnemyCatalog {
+    pub fn index_of(&self, id: &str) -> Option<usize> {
+        self.archetypes
+            .iter()
+            .position(|archetype| archetype.id == id)
+    }
+
+    /// The boss archetype that wave `wave` is built around, if any. The first
+    /// matching milestone wins.
+    pub fn boss_for_wave(&self, wave: u32) -> Option<usize> {
+        self.boss_waves
+            .iter()
+            .filter(|milestone| milestone.matches(wave))
+            .find_map(|milestone| self.index_of(&milestone.archetype))
+    }
+
+    /// Archetype ids referenced by the spawn table, a split, a scripted wave
+    /// or a boss that aren't defined. They are ignored at runtime.
+    pub fn unknown_ids(&self) -> Vec<&str> {
+        let stage_ids = self
+            .spawn_table
+            .iter()
+            .flat_map(|stage| stage.weights.keys());
+        let split_ids = self
+            .archetypes
+            .iter()
+            .filter_map(|archetype| archetype.split.as_ref())
+            .map(|split| &split.archetype);
+        let wave_ids = self
+            .waves
+            .iter()
+            .flat_map(|wave| &wave.groups)
+            .map(|group| &group.archetype);
+        let boss_ids = self.boss_waves.iter().map(|milestone| &milestone.archetype);
+        let summon_ids = self
+            .archetypes
+            .iter()
+            .filter_map(|archetype| archetype.boss.as_ref())
+            .flat_map(|boss| &boss.phases)
+            .flat_map(|phase| &phase.patterns)
+            .filter_map(|pattern| match pattern {
+                BossPattern::Summon { archetype, .. } => Some(archetype),
+                _ => None,
+            });
+        let mut unknown: Vec<&str> = stage_ids
+            .chain(split_ids)
+            .chain(wave_ids)
+            .chain(boss_ids)
+            .chain(summon_ids)
+            .map(String::as_str)
+            .filter(|id| self.index_of(id).is_none())
+            .collect();
+        unknown.sort_unstable();
+        unknown.dedup();
+        unknown
+    }
+
+    /// Archetype ids that a boss milestone points at but that have no `boss`
+    /// spec. Those waves spawn an ordinary enemy in the boss's place.
+    pub fn non_boss_milestones(&self) -> Vec<&str> {
+        let mut ids: Vec<&str> = self
+            .boss_waves
+            .iter()
+            .map(|milestone| milestone.archetype.as_str())
+            .filter(|id| {
+                self.index_of(id)
+                    .is_some_and(|index| self.archetypes[index].boss.is_none())
+            })
+            .collect();
+        ids.sort_unstable();
+        ids.dedup();
+        ids
+    }
+
+    /// Spawn weight of each archetype, by index, `elapsed` seconds into a run.
+    pub fn weights_at(&self, elapsed: f32) -> Vec<f32> {
+        let weight = |stage: &SpawnStage, id: &str| stage.weights.get(id).copied().unwrap_or(0.0);
+
+        let next = self
+            .spawn_table
+            .iter()
+            .position(|stage| stage.at > elapsed)
+            .unwrap_or(self.spawn_table.len());
+        let (from, to) = match next {
+            0 => (self.spawn_table.first(), None),
+            n => (self.spawn_table.get(n - 1), self.spawn_table.get(n)),
+        };
+
+        self.archetypes
+            .iter()
+            .map(|archetype| match (from, to) {
+                (Some(from), Some(to)) if to.at > from.at && elapsed >= from.at => {
+                    let t = (elapsed - from.at) / (to.at - from.at);
+                    let a = weight(from, &archetype.id);
+                    a + (weight(to, &archetype.id) - a) * t
+                }
+                (Some(stage), _) => weight(stage, &archetype.id),
+                (None, _) => 0.0,
+            })
+            .map(|weight| weight.max(0.0))
+            .collect()
+    }
+
+    /// Picks an archetype for `elapsed` seconds into a run from a uniform
+    /// `roll` in `0..1`. `None` when nothing has a positive weight.
+    pub fn pick(&self, elapsed: f32, roll: f32) -> Option<usize> {
+        let weights = self.weights_at(elapsed);
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut remaining = roll * total;
+        for (index, weight) in weights.iter().enumerate() {
+            if *weight > 0.0 && remaining < *weight {
+                return Some(index);
+            }
+            remaining -= weight;
+        }
+        weights.iter().rposition(|weight| *weight > 0.0)
+    }
+
+    /// A hash of every value in the catalog, the same on every platform and
+    /// build, so a replay can tell it was recorded against other enemies.
+    pub fn fingerprint(&self) -> u64 {
+        let mut canonical = String::new();
+        write_canonical(
+            &serde_json::to_value(self).unwrap_or_default(),
+            &mut canonical,
+        );
+        // 64-bit FNV-1a.
+        canonical.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        })
+    }
+}
+
+/// Writes `value` as JSON with object keys sorted, so map order doesn't
+/// change the result.
+fn write_canonical(value: &serde_json::Value, out: &mut String) {
+    use serde_json::Value;
+
+    match value {
+        Value::Object(map) => {
+            let mut keys: Vec<&String> = map.keys().collect();
+            keys.sort_unstable();
+            out.push('{');
+            for (i, key) in keys.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(&map[key], out);
+            }
+            out.push('}');
+        }
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(item, out);
+            }
+            out.push(']');
+        }
+        other => out.push_str(&other.to_string()),
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// The catalog the game ships with.
+    pub(crate) fn shipped_catalog() -> EnemyCatalog {
+        serde_json::from_str(include_str!("../../../assets/data/enemies.json"))
+            .expect("enemies.json should parse")
+    }
+
+    fn phases(belows: &[f32]) -> BossSpec {
+        BossSpec {
+            name: "Test".into(),
+            phases: belows
+                .iter()
+                .map(|&below| BossPhase {
+                    below,
+                    speed: 100.0,
+                    cooldown: 1.0,
+                    patterns: Vec::new(),
+                })
+                .collect(),
+            reward: BossReward::default(),
+        }
+    }
+
+    fn milestone(wave: u32, repeat_every: Option<u32>) -> BossMilestone {
+        BossMilestone {
+            wave,
+            archetype: "boss".into(),
+            repeat_every,
+        }
+    }
+
+    #[test]
+    fn shipped_catalog_is_consistent() {
+        let catalog = shipped_catalog();
+
+        assert!(
+            catalog.unknown_ids().is_empty(),
+            "{:?}",
+            catalog.unknown_ids()
+        );
+        assert!(catalog.non_boss_milestones().is_empty());
+        for (index, archetype) in catalog.archetypes.iter().enumerate() {
+            assert_eq!(catalog.index_of(&archetype.id), Some(index), "duplicate id");
+        }
+    }
+
+    #[test]
+    fn unknown_ids_are_reported_once() {
+        let catalog = EnemyCatalog {
+            boss_waves: vec![milestone(5, None), milestone(10, None)],
+            ..EnemyCatalog::default()
+        };
+
+        assert_eq!(catalog.unknown_ids(), ["boss"]);
+    }
+
+    #[test]
+    fn plain_archetypes_are_not_bosses() {
+        let catalog = EnemyCatalog {
+            boss_waves: vec![BossMilestone {
+                wave: 5,
+                archetype: "chaser".into(),
+                repeat_every: None,
+            }],
+            ..EnemyCatalog::default()
+        };
+
+        assert_eq!(catalog.non_boss_milestones(), ["chaser"]);
+    }
+
+    #[test]
+    fn milestones_match_their_wave_and_repeats() {
+        let once = milestone(5, None);
+        assert!(!once.matches(4));
+        assert!(once.matches(5));
+        assert!(!once.matches(10));
+
+        let repeating = milestone(5, Some(10));
+        assert!(!repeating.matches(4));
+        assert!(repeating.matches(5));
+        assert!(!repeating.matches(10));
+        assert!(repeating.matches(15));
+        assert!(repeating.matches(25));
+        assert!(!repeating.matches(26));
+
+        // Zero would divide by zero; it means "never repeat".
+        let zero = milestone(5, Some(0));
+        assert!(zero.matches(5));
+        assert!(!zero.matches(10));
+    }
+
+    #[test]
+    fn shipped_boss_waves() {
+        let catalog = shipped_catalog();
+        let boss = |wave| {
+            catalog
+                .boss_for_wave(wave)
+                .map(|index| catalog.archetypes[index].id.as_str())
+        };
+
+        assert_eq!(boss(4), None);
+        assert_eq!(boss(5), Some("warden"));
+        assert_eq!(boss(10), Some("loom_queen"));
+        assert_eq!(boss(15), Some("warden"));
+        assert_eq!(boss(20), Some("loom_queen"));
+        assert_eq!(boss(21), None);
+    }
+
+    #[test]
+    fn phases_start_at_their_threshold() {
+        let spec = phases(&[1.0, 0.66, 0.33]);
+
+        assert_eq!(spec.phase_at(1.0), 0);
+        assert_eq!(spec.phase_at(0.67), 0);
+        assert_eq!(spec.phase_at(0.66), 1);
+        assert_eq!(spec.phase_at(0.34), 1);
+        assert_eq!(spec.phase_at(0.33), 2);
+        assert_eq!(spec.phase_at(0.0), 2);
+    }
+
+    #[test]
+    fn health_above_every_phase_uses_the_first() {
+        let spec = phases(&[0.5]);
+
+        assert_eq!(spec.phase_at(0.9), 0);
+        assert_eq!(phases(&[]).phase_at(0.5), 0);
+    }
+
+    #[test]
+    fn shipped_bosses_order_phases_from_full_health() {
+        for archetype in shipped_catalog().archetypes {
+            let Some(boss) = archetype.boss else {
+                continue;
+            };
+            assert_eq!(boss.phases.first().map(|phase| phase.below), Some(1.0));
+            assert!(boss
+                .phases
+                .windows(2)
+                .all(|pair| pair[0].below > pair[1].below));
+            assert_eq!(boss.phase_at(0.0), boss.phases.len() - 1);
+        }
+    }
+
+    #[test]
+    fn fingerprint_follows_values_not_map_order() {
+        let catalog = shipped_catalog();
+        let mut reordered = catalog.clone();
+        for stage in &mut reordered.spawn_table {
+            let mut weights: Vec<_> = stage.weights.drain().collect();
+            weights.reverse();
+            stage.weights = weights.into_iter().collect();
+        }
+        assert_eq!(catalog.fingerprint(), reordered.fingerprint());
+
+        let mut faster = catalog.clone();
+        faster.archetypes[0].speed += 1.0;
+        assert_ne!(catalog.fingerprint(), faster.fingerprint());
+        assert_ne!(catalog.fingerprint(), EnemyCatalog::default().fingerprint());
+    }
+}
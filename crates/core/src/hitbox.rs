@@ -0,0 +1,326 @@
+use crate::util::distance_squared_to_segment;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Collision shape of an entity, centred on its transform. Every overlap
+/// check goes through these rather than fixed radii.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Hitbox {
+    Circle {
+        radius: f32,
+    },
+    /// Axis-aligned box; ignores the entity's rotation.
+    Aabb {
+        half_width: f32,
+        half_height: f32,
+    },
+    /// A segment `2 * half_length` long along the entity's local x axis,
+    /// swept by `radius`.
+    Capsule {
+        half_length: f32,
+        radius: f32,
+    },
+}
+
+impl Hitbox {
+    /// The hitbox placed at `transform` in world space.
+    pub fn placed(&self, transform: &Transform) -> Shape {
+        let center = transform.translation.truncate();
+        match *self {
+            Self::Circle { radius } => Shape::circle(center, radius),
+            Self::Aabb {
+                half_width,
+                half_height,
+            } => Shape::aabb(center, Vec2::new(half_width, half_height)),
+            Self::Capsule {
+                half_length,
+                radius,
+            } => {
+                let axis = (transform.rotation * Vec3::X).truncate() * half_length;
+                Shape::capsule(center - axis, center + axis, radius)
+            }
+        }
+    }
+}
+
+/// The solid part of a `Shape`, before its radius is swept around it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Core {
+    Point(Vec2),
+    Segment(Vec2, Vec2),
+    Box { min: Vec2, max: Vec2 },
+}
+
+/// A hitbox in world space: everything within `radius` of `core`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Shape {
+    pub core: Core,
+    pub radius: f32,
+}
+
+impl Shape {
+    pub fn circle(center: Vec2, radius: f32) -> Self {
+        Self {
+            core: Core::Point(center),
+            radius,
+        }
+    }
+
+    pub fn capsule(a: Vec2, b: Vec2, radius: f32) -> Self {
+        Self {
+            core: Core::Segment(a, b),
+            radius,
+        }
+    }
+
+    pub fn aabb(center: Vec2, half_size: Vec2) -> Self {
+        Self {
+            core: Core::Box {
+                min: center - half_size,
+                max: center + half_size,
+            },
+            radius: 0.0,
+        }
+    }
+
+    /// Corners of the smallest axis-aligned box holding the shape.
+    pub fn bounds(&self) -> (Vec2, Vec2) {
+        let (min, max) = match self.core {
+            Core::Point(point) => (point, point),
+            Core::Segment(a, b) => (a.min(b), a.max(b)),
+            Core::Box { min, max } => (min, max),
+        };
+        (min - self.radius, max + self.radius)
+    }
+
+    /// Middle of the shape's bounds.
+    pub fn center(&self) -> Vec2 {
+        let (min, max) = self.bounds();
+        (min + max) * 0.5
+    }
+
+    pub fn overlaps(&self, other: &Shape) -> bool {
+        let reach = self.radius + other.radius;
+        core_distance_squared(self.core, other.core) <= reach * reach
+    }
+}
+
+fn core_distance_squared(a: Core, b: Core) -> f32 {
+    match (a, b) {
+        (Core::Point(p), Core::Point(q)) => p.distance_squared(q),
+        (Core::Point(p), Core::Segment(a, b)) | (Core::Segment(a, b), Core::Point(p)) => {
+            distance_squared_to_segment(p, a, b)
+        }
+        (Core::Point(p), Core::Box { min, max }) | (Core::Box { min, max }, Core::Point(p)) => {
+            p.distance_squared(p.clamp(min, max))
+        }
+        (Core::Segment(a, b), Core::Segment(c, d)) => {
+            if segments_cross(a, b, c, d) {
+                return 0.0;
+            }
+            distance_squared_to_segment(a, c, d)
+                .min(distance_squared_to_segment(b, c, d))
+                .min(distance_squared_to_segment(c, a, b))
+                .min(distance_squared_to_segment(d, a, b))
+        }
+        (Core::Segment(a, b), Core::Box { min, max })
+        | (Core::Box { min, max }, Core::Segment(a, b)) => {
+            let corners = [min, Vec2::new(max.x, min.y), max, Vec2::new(min.x, max.y)];
+            let inside = |p: Vec2| p.cmpge(min).all() && p.cmple(max).all();
+            if inside(a)
+                || inside(b)
+                || (0..4).any(|i| segments_cross(a, b, corners[i], corners[(i + 1) % 4]))
+            {
+                return 0.0;
+            }
+            // Apart, the closest pair always involves an endpoint or a corner.
+            let to_box = |p: Vec2| p.distance_squared(p.clamp(min, max));
+            corners
+                .iter()
+                .map(|&corner| distance_squared_to_segment(corner, a, b))
+                .fold(to_box(a).min(to_box(b)), f32::min)
+        }
+        (
+            Core::Box {
+                min: a_min,
+                max: a_max,
+            },
+            Core::Box {
+                min: b_min,
+                max: b_max,
+            },
+        ) => {
+            let gap = (a_min - b_max).max(b_min - a_max).max(Vec2::ZERO);
+            gap.length_squared()
+        }
+    }
+}
+
+/// Whether segments `a`-`b` and `c`-`d` cross or touch. Collinear pairs
+/// report `false`; their endpoint distances already cover them.
+fn segments_cross(a: Vec2, b: Vec2, c: Vec2, d: Vec2) -> bool {
+    let d1 = (b - a).perp_dot(c - a);
+    let d2 = (b - a).perp_dot(d - a);
+    if d1 == 0.0 && d2 == 0.0 {
+        return false;
+    }
+    let d3 = (d - c).perp_dot(a - c);
+    let d4 = (d - c).perp_dot(b - c);
+    d1 * d2 <= 0.0 && d3 * d4 <= 0.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn point(x: f32, y: f32) -> Core {
+        Core::Point(Vec2::new(x, y))
+    }
+
+    fn segment(ax: f32, ay: f32, bx: f32, by: f32) -> Core {
+        Core::Segment(Vec2::new(ax, ay), Vec2::new(bx, by))
+    }
+
+    fn square(x: f32, y: f32, half: f32) -> Core {
+        Core::Box {
+            min: Vec2::new(x - half, y - half),
+            max: Vec2::new(x + half, y + half),
+        }
+    }
+
+    /// Distance between two cores, checked to agree in both orders.
+    fn distance(a: Core, b: Core) -> f32 {
+        let forward = core_distance_squared(a, b);
+        assert_eq!(forward, core_distance_squared(b, a), "{a:?} vs {b:?}");
+        forward.sqrt()
+    }
+
+    #[test]
+    fn point_point() {
+        assert_eq!(distance(point(1.0, 1.0), point(1.0, 1.0)), 0.0);
+        assert_eq!(distance(point(0.0, 0.0), point(3.0, 4.0)), 5.0);
+    }
+
+    #[test]
+    fn point_segment() {
+        // Touching an end, lying on it, and apart beside and past its ends.
+        assert_eq!(distance(point(0.0, 0.0), segment(0.0, 0.0, 4.0, 0.0)), 0.0);
+        assert_eq!(distance(point(2.0, 0.0), segment(0.0, 0.0, 4.0, 0.0)), 0.0);
+        assert_eq!(distance(point(2.0, 3.0), segment(0.0, 0.0, 4.0, 0.0)), 3.0);
+        assert_eq!(distance(point(7.0, 0.0), segment(0.0, 0.0, 4.0, 0.0)), 3.0);
+    }
+
+    #[test]
+    fn point_box() {
+        assert_eq!(distance(point(0.5, 0.5), square(0.0, 0.0, 1.0)), 0.0);
+        assert_eq!(distance(point(1.0, 0.0), square(0.0, 0.0, 1.0)), 0.0);
+        assert_eq!(distance(point(3.0, 0.0), square(0.0, 0.0, 1.0)), 2.0);
+        assert_eq!(distance(point(4.0, 5.0), square(0.0, 0.0, 1.0)), 5.0);
+    }
+
+    #[test]
+    fn segment_segment() {
+        // Crossing, touching at an endpoint, T-junction and parallel apart.
+        assert_eq!(
+            distance(segment(-1.0, 0.0, 1.0, 0.0), segment(0.0, -1.0, 0.0, 1.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(0.0, 0.0, 1.0, 0.0), segment(1.0, 0.0, 1.0, 5.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(-1.0, 0.0, 1.0, 0.0), segment(0.0, 0.0, 0.0, 3.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(0.0, 0.0, 4.0, 0.0), segment(0.0, 2.0, 4.0, 2.0)),
+            2.0
+        );
+    }
+
+    #[test]
+    fn collinear_segments() {
+        // Overlapping, one inside the other, and apart along the same line.
+        assert_eq!(
+            distance(segment(0.0, 0.0, 4.0, 0.0), segment(2.0, 0.0, 6.0, 0.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(0.0, 0.0, 10.0, 0.0), segment(3.0, 0.0, 4.0, 0.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(0.0, 0.0, 4.0, 0.0), segment(7.0, 0.0, 9.0, 0.0)),
+            3.0
+        );
+    }
+
+    #[test]
+    fn segment_box() {
+        // Passing straight through, ending inside, grazing an edge along its
+        // length, and apart.
+        assert_eq!(
+            distance(segment(-5.0, 0.0, 5.0, 0.0), square(0.0, 0.0, 1.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(0.0, 0.0, 5.0, 5.0), square(0.0, 0.0, 1.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(-5.0, 1.0, 5.0, 1.0), square(0.0, 0.0, 1.0)),
+            0.0
+        );
+        assert_eq!(
+            distance(segment(-5.0, 4.0, 5.0, 4.0), square(0.0, 0.0, 1.0)),
+            3.0
+        );
+        // Diagonal past a corner: closest to the corner, not an endpoint.
+        let gap = distance(segment(0.0, 4.0, 4.0, 0.0), square(0.0, 0.0, 1.0));
+        assert!((gap - std::f32::consts::SQRT_2).abs() < 1e-5);
+    }
+
+    #[test]
+    fn box_box() {
+        assert_eq!(distance(square(0.0, 0.0, 1.0), square(1.0, 1.0, 1.0)), 0.0);
+        assert_eq!(distance(square(0.0, 0.0, 1.0), square(2.0, 0.0, 1.0)), 0.0);
+        assert_eq!(distance(square(0.0, 0.0, 1.0), square(5.0, 0.0, 1.0)), 3.0);
+        assert_eq!(distance(square(0.0, 0.0, 1.0), square(5.0, 6.0, 1.0)), 5.0);
+    }
+
+    #[test]
+    fn radii_extend_the_reach() {
+        let circle = Shape::circle(Vec2::ZERO, 1.0);
+
+        assert!(circle.overlaps(&Shape::circle(Vec2::new(2.0, 0.0), 1.0)));
+        assert!(!circle.overlaps(&Shape::circle(Vec2::new(2.1, 0.0), 1.0)));
+        assert!(circle.overlaps(&Shape::capsule(
+            Vec2::new(-3.0, 1.5),
+            Vec2::new(3.0, 1.5),
+            0.5
+        )));
+        assert!(!circle.overlaps(&Shape::aabb(Vec2::new(3.0, 0.0), Vec2::ONE)));
+    }
+
+    #[test]
+    fn capsules_follow_rotation() {
+        let hitbox = Hitbox::Capsule {
+            half_length: 2.0,
+            radius: 0.5,
+        };
+        let upright = Transform::from_xyz(1.0, 1.0, 0.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2));
+        let Shape {
+            core: Core::Segment(a, b),
+            ..
+        } = hitbox.placed(&upright)
+        else {
+            panic!("a capsule places as a segment");
+        };
+
+        assert!(a.abs_diff_eq(Vec2::new(1.0, -1.0), 1e-5));
+        assert!(b.abs_diff_eq(Vec2::new(1.0, 3.0), 1e-5));
+    }
+}
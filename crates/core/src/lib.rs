@@ -1,12 +1,22 @@
 pub mod components;
 pub mod constants;
+pub mod enemies;
+pub mod hitbox;
+pub mod replay;
 pub mod resources;
 pub mod shop;
+pub mod state;
 pub mod util;
+pub mod waves;
 
 pub mod prelude {
     pub use super::components::*;
     pub use super::constants::*;
+    pub use super::enemies::*;
+    pub use super::hitbox::*;
+    pub use super::replay::*;
     pub use super::resources::*;
     pub use super::shop::*;
+    pub use super::state::*;
+    pub use super::waves::*;
 }
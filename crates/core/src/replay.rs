@@ -0,0 +1,209 @@
+use crate::components::WeaponType;
+use crate::constants::SIMULATION_HZ;
+use crate::enemies::EnemyCatalog;
+use crate::resources::PurchasedUpgrades;
+use crate::shop::UpgradeType;
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Bump whenever a change would make existing replays play out differently.
+pub const REPLAY_VERSION: u32 = 1;
+
+/// A run's input on every simulation step, plus the seed and starting save
+/// needed to play it back to the same end.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    /// Simulation steps per second the ticks were recorded at.
+    pub tick_rate: f64,
+    pub seed: u64,
+    /// `EnemyCatalog::fingerprint` of the enemies the run was played against.
+    #[serde(default)]
+    pub catalog: u64,
+    pub currency: u32,
+    pub upgrades: PurchasedUpgrades,
+    pub weapon: WeaponType,
+    /// Pointer target on each tick, as runs of `(x, y, ticks)`.
+    pointer: Vec<(f32, f32, u32)>,
+    /// Ticks on which the weapon was switched, ascending.
+    pub weapon_switches: Vec<u32>,
+    /// Shop purchases, each with the tick it was made before.
+    pub purchases: Vec<(u32, UpgradeType)>,
+    pub ticks: u32,
+    /// Score the recorded run ended on.
+    pub score: u32,
+}
+
+impl Replay {
+    pub fn new(
+        seed: u64,
+        catalog: u64,
+        currency: u32,
+        upgrades: PurchasedUpgrades,
+        weapon: WeaponType,
+    ) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            tick_rate: SIMULATION_HZ,
+            seed,
+            catalog,
+            currency,
+            upgrades,
+            weapon,
+            pointer: Vec::new(),
+            weapon_switches: Vec::new(),
+            purchases: Vec::new(),
+            ticks: 0,
+            score: 0,
+        }
+    }
+
+    /// Appends one tick of input.
+    pub fn record_tick(&mut self, target: Vec2, switch_weapon: bool) {
+        if switch_weapon {
+            self.weapon_switches.push(self.ticks);
+        }
+        match self.pointer.last_mut() {
+            Some((x, y, ticks)) if *x == target.x && *y == target.y => *ticks += 1,
+            _ => self.pointer.push((target.x, target.y, 1)),
+        }
+        self.ticks += 1;
+    }
+
+    /// The pointer target on every tick, in order.
+    pub fn pointer_targets(&self) -> Vec<Vec2> {
+        self.pointer
+            .iter()
+            .flat_map(|&(x, y, ticks)| std::iter::repeat_n(Vec2::new(x, y), ticks as usize))
+            .collect()
+    }
+
+    /// Whether this build steps the simulation the way the replay was
+    /// recorded, against the same enemies.
+    pub fn is_compatible(&self, catalog: &EnemyCatalog) -> bool {
+        self.version == REPLAY_VERSION
+            && self.tick_rate == SIMULATION_HZ
+            && self.catalog == catalog.fingerprint()
+    }
+}
+
+/// How a replay's score compared with the one it was recorded on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ReplayCheck {
+    pub recorded: u32,
+    pub reached: u32,
+}
+
+impl ReplayCheck {
+    /// Whether playback ended somewhere the recorded run didn't, i.e. the
+    /// simulation isn't deterministic on this machine.
+    pub fn diverged(&self) -> bool {
+        self.recorded != self.reached
+    }
+}
+
+impl Default for Replay {
+    fn default() -> Self {
+        Self::new(0, 0, 0, PurchasedUpgrades::default(), WeaponType::Trail)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn recorded(targets: &[Vec2]) -> Replay {
+        let mut replay = Replay::default();
+        for &target in targets {
+            replay.record_tick(target, false);
+        }
+        replay
+    }
+
+    #[test]
+    fn pointer_targets_round_trip() {
+        let targets = [
+            Vec2::ZERO,
+            Vec2::ZERO,
+            Vec2::ZERO,
+            Vec2::new(12.5, -3.0),
+            Vec2::new(12.5, -3.0),
+            Vec2::ZERO,
+            Vec2::new(-0.1, 7.25),
+        ];
+        let replay = recorded(&targets);
+
+        assert_eq!(replay.pointer_targets(), targets);
+        assert_eq!(replay.ticks, targets.len() as u32);
+    }
+
+    #[test]
+    fn steady_pointer_is_one_run() {
+        let replay = recorded(&[Vec2::new(40.0, 40.0); 600]);
+
+        assert_eq!(replay.pointer, vec![(40.0, 40.0, 600)]);
+    }
+
+    #[test]
+    fn nearly_equal_targets_stay_separate() {
+        let nudged = Vec2::new(1.0 + f32::EPSILON, 1.0);
+        let replay = recorded(&[Vec2::ONE, nudged]);
+
+        assert_eq!(replay.pointer.len(), 2);
+        assert_eq!(replay.pointer_targets(), [Vec2::ONE, nudged]);
+    }
+
+    #[test]
+    fn empty_replay_has_no_targets() {
+        let replay = Replay::default();
+
+        assert!(replay.pointer_targets().is_empty());
+        assert_eq!(replay.ticks, 0);
+    }
+
+    #[test]
+    fn weapon_switches_record_their_tick() {
+        let mut replay = Replay::default();
+        for tick in 0..10 {
+            replay.record_tick(Vec2::ZERO, tick == 3 || tick == 7);
+        }
+
+        assert_eq!(replay.weapon_switches, [3, 7]);
+        assert_eq!(replay.pointer, vec![(0.0, 0.0, 10)]);
+    }
+
+    #[test]
+    fn compatibility_needs_version_tick_rate_and_catalog() {
+        let catalog = EnemyCatalog::default();
+        let replay = Replay {
+            catalog: catalog.fingerprint(),
+            ..Replay::default()
+        };
+        assert!(replay.is_compatible(&catalog));
+
+        let newer = Replay {
+            version: REPLAY_VERSION + 1,
+            ..replay.clone()
+        };
+        assert!(!newer.is_compatible(&catalog));
+
+        let slower = Replay {
+            tick_rate: SIMULATION_HZ / 2.0,
+            ..replay.clone()
+        };
+        assert!(!slower.is_compatible(&catalog));
+
+        let mut edited = catalog.clone();
+        edited.archetypes[0].speed *= 2.0;
+        assert!(!replay.is_compatible(&edited));
+    }
+
+    #[test]
+    fn replays_from_before_the_catalog_check_are_incompatible() {
+        let mut json = serde_json::to_value(Replay::default()).unwrap();
+        json.as_object_mut().unwrap().remove("catalog");
+        let old: Replay = serde_json::from_value(json).unwrap();
+
+        assert!(!old.is_compatible(&EnemyCatalog::default()));
+    }
+}
@@ -1,60 +1,82 @@
+use crate::components::KillSource;
+use crate::constants::{
+    ARENA_SIZE, COMBO_MAX_MULTIPLIER, COMBO_MILESTONES, COMBO_MULTIPLIER_STEP, COMBO_WINDOW,
+    PARTICLE_BUDGET, RUN_HISTORY_LIMIT,
+};
+use crate::enemies::EnemyCatalog;
+use crate::replay::ReplayCheck;
 use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
 
 #[derive(Resource, Default)]
-pub struct RunState {
-    active: bool,
-    paused: bool,
+pub struct PointerTarget {
+    pub position: Vec2,
 }
 
-impl RunState {
-    pub fn new() -> Self {
-        Self {
-            active: true,
-            paused: false,
-        }
-    }
+/// Set by input when the player asks for the other weapon; the next
+/// simulation step swaps it.
+#[derive(Resource, Default)]
+pub struct WeaponSwitch {
+    pub requested: bool,
+}
 
-    pub fn is_running(&self) -> bool {
-        self.active && !self.paused
-    }
+/// Outline of the playable area, centred on the origin.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ArenaShape {
+    Rect { size: Vec2 },
+    Circle { radius: f32 },
+}
 
-    pub fn is_active(&self) -> bool {
-        self.active
+/// The playable area. Player and pointer clamping, enemy spawns, the
+/// background and the visible wall all follow it; insert one before
+/// `GameplayPlugin` to change the world.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct Arena {
+    pub shape: ArenaShape,
+}
+
+impl Default for Arena {
+    fn default() -> Self {
+        Self::rect(Vec2::splat(ARENA_SIZE))
     }
+}
 
-    pub fn pause(&mut self) {
-        self.paused = true;
+impl Arena {
+    pub fn rect(size: Vec2) -> Self {
+        Self {
+            shape: ArenaShape::Rect { size },
+        }
     }
 
-    pub fn resume(&mut self) {
-        if self.active {
-            self.paused = false;
+    pub fn circle(radius: f32) -> Self {
+        Self {
+            shape: ArenaShape::Circle { radius },
         }
     }
 
-    pub fn reset(&mut self) {
-        self.active = true;
-        self.paused = false;
+    /// Size of the axis-aligned box around the arena.
+    pub fn size(&self) -> Vec2 {
+        match self.shape {
+            ArenaShape::Rect { size } => size,
+            ArenaShape::Circle { radius } => Vec2::splat(radius * 2.0),
+        }
     }
 
-    pub fn end(&mut self) {
-        self.active = false;
+    pub fn contains(&self, point: Vec2) -> bool {
+        self.clamp(point) == point
     }
 
-    pub fn toggle_pause(&mut self) {
-        if self.active {
-            self.paused = !self.paused;
+    /// The closest point to `point` inside the arena.
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        match self.shape {
+            ArenaShape::Rect { size } => point.clamp(-size * 0.5, size * 0.5),
+            ArenaShape::Circle { radius } => point.clamp_length_max(radius),
         }
     }
 }
 
-#[derive(Resource, Default)]
-pub struct PointerTarget {
-    pub position: Vec2,
-}
-
 #[derive(Resource, Default)]
 pub struct PlayerHealth {
     pub current: u32,
@@ -117,6 +139,174 @@ impl Score {
     }
 }
 
+/// Kill chain for the current run. Each kill refreshes the combo window;
+/// letting the window run out or taking a hit drops the multiplier back to 1.
+#[derive(Resource)]
+pub struct ComboState {
+    pub count: u32,
+    pub multiplier: f32,
+    pub window: f32,
+    pub peak: u32,
+}
+
+impl Default for ComboState {
+    fn default() -> Self {
+        Self {
+            count: 0,
+            multiplier: 1.0,
+            window: 0.0,
+            peak: 0,
+        }
+    }
+}
+
+impl ComboState {
+    /// Records a kill and returns the new chain length.
+    pub fn register_kill(&mut self) -> u32 {
+        self.count = if self.window > 0.0 { self.count + 1 } else { 1 };
+        self.window = COMBO_WINDOW;
+        self.multiplier =
+            (1.0 + (self.count - 1) as f32 * COMBO_MULTIPLIER_STEP).min(COMBO_MAX_MULTIPLIER);
+        self.peak = self.peak.max(self.count);
+        self.count
+    }
+
+    pub fn apply(&self, amount: u32) -> u32 {
+        (amount as f32 * self.multiplier).round() as u32
+    }
+
+    /// Advances the combo window, breaking the chain once it lapses.
+    pub fn tick(&mut self, delta: f32) {
+        if self.window <= 0.0 {
+            return;
+        }
+        self.window = (self.window - delta).max(0.0);
+        if self.window <= 0.0 {
+            self.break_chain();
+        }
+    }
+
+    pub fn break_chain(&mut self) {
+        self.count = 0;
+        self.multiplier = 1.0;
+        self.window = 0.0;
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.count > 1
+    }
+
+    /// Whether the chain has just reached one of `COMBO_MILESTONES`.
+    pub fn at_milestone(&self) -> bool {
+        COMBO_MILESTONES.contains(&self.count)
+    }
+}
+
+/// Tallies collected while a run is in progress.
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RunStats {
+    pub time_survived: f32,
+    pub trail_kills: u32,
+    pub wave_kills: u32,
+    pub contact_kills: u32,
+    pub damage_taken: u32,
+    pub pickups: u32,
+    pub currency_earned: u32,
+    pub peak_combo: u32,
+}
+
+impl RunStats {
+    pub fn record_kill(&mut self, source: KillSource) {
+        match source {
+            KillSource::Trail => self.trail_kills += 1,
+            KillSource::Wave => self.wave_kills += 1,
+            KillSource::Contact => self.contact_kills += 1,
+        }
+    }
+
+    pub fn total_kills(&self) -> u32 {
+        self.trail_kills + self.wave_kills + self.contact_kills
+    }
+}
+
+/// The highest-scoring run so far and the stats it finished with.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct BestRun {
+    pub score: u32,
+    pub stats: RunStats,
+}
+
+impl BestRun {
+    /// The best run a save remembers: the top run in its history, with the
+    /// score raised to `best_score`, which outlives the capped history.
+    pub fn from_save(history: &RunHistory, best_score: u32) -> Self {
+        let mut best = history.best().map_or_else(Self::default, |record| Self {
+            score: record.score,
+            stats: record.stats.clone(),
+        });
+        best.score = best.score.max(best_score);
+        best
+    }
+}
+
+/// A finished run as it is written to the save.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RunRecord {
+    /// Seconds since the Unix epoch when the run ended.
+    pub timestamp: u64,
+    pub score: u32,
+    pub seed: Option<u64>,
+    #[serde(default)]
+    pub stats: RunStats,
+}
+
+impl RunRecord {
+    pub fn duration(&self) -> f32 {
+        self.stats.time_survived
+    }
+
+    pub fn kills(&self) -> u32 {
+        self.stats.total_kills()
+    }
+}
+
+/// Most recent runs, newest last, capped at `RUN_HISTORY_LIMIT`.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct RunHistory {
+    pub records: Vec<RunRecord>,
+}
+
+impl RunHistory {
+    pub fn push(&mut self, record: RunRecord) {
+        self.records.push(record);
+        if self.records.len() > RUN_HISTORY_LIMIT {
+            let overflow = self.records.len() - RUN_HISTORY_LIMIT;
+            self.records.drain(..overflow);
+        }
+    }
+
+    pub fn best(&self) -> Option<&RunRecord> {
+        self.records.iter().max_by_key(|record| record.score)
+    }
+}
+
+/// The run that just ended, alongside the best run as it stood beforehand.
+#[derive(Resource, Default, Clone, Debug)]
+pub struct RunSummary {
+    pub score: u32,
+    pub seed: u64,
+    pub stats: RunStats,
+    pub previous_best: BestRun,
+    pub new_best: bool,
+    /// Set when the run was a replay.
+    pub replay: Option<ReplayCheck>,
+}
+
 #[derive(Resource, Default)]
 pub struct Currency {
     pub balance: u32,
@@ -137,7 +327,8 @@ impl Currency {
     }
 }
 
-#[derive(Resource, Clone, Serialize, Deserialize)]
+#[derive(Resource, Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
 pub struct PurchasedUpgrades {
     pub movement_speed_level: u32,
     pub max_health_level: u32,
@@ -145,24 +336,13 @@ pub struct PurchasedUpgrades {
     pub shield_level: u32,
 }
 
-impl Default for PurchasedUpgrades {
-    fn default() -> Self {
-        Self {
-            movement_speed_level: 0,
-            max_health_level: 0,
-            trail_damage_level: 0,
-            shield_level: 0,
-        }
-    }
-}
-
 impl PurchasedUpgrades {
     pub fn movement_speed_multiplier(&self) -> f32 {
         1.0 + self.movement_speed_level as f32 * 0.1
     }
 
     pub fn max_health_bonus(&self) -> u32 {
-        self.max_health_level * 1
+        self.max_health_level
     }
 
     pub fn trail_damage_multiplier(&self) -> f32 {
@@ -176,29 +356,9 @@ impl PurchasedUpgrades {
 
 #[derive(Resource, Default)]
 pub struct ShopState {
-    pub is_open: bool,
     pub selected_index: usize,
 }
 
-impl ShopState {
-    pub fn toggle(&mut self) {
-        self.is_open = !self.is_open;
-    }
-}
-
-#[derive(Resource, Default)]
-pub struct EnemySpawnTimer {
-    pub timer: Timer,
-}
-
-impl EnemySpawnTimer {
-    pub fn new(interval: f32) -> Self {
-        let mut timer = Timer::from_seconds(interval, TimerMode::Repeating);
-        timer.tick(Duration::from_secs_f32(interval));
-        Self { timer }
-    }
-}
-
 #[derive(Resource, Default)]
 pub struct TrailSpawnTimer {
     pub timer: Timer,
@@ -216,12 +376,34 @@ impl ShieldState {
     }
 }
 
+/// Live particle count, kept under `PARTICLE_BUDGET`. Spawns past the budget
+/// are dropped rather than queued.
+#[derive(Resource, Default)]
+pub struct ParticleBudget {
+    pub live: usize,
+}
+
+impl ParticleBudget {
+    /// Takes room for up to `wanted` more particles and returns how many fit.
+    pub fn reserve(&mut self, wanted: usize) -> usize {
+        let granted = wanted.min(PARTICLE_BUDGET.saturating_sub(self.live));
+        self.live += granted;
+        granted
+    }
+
+    /// Replaces the count with the particles still alive. Recounting every
+    /// frame means particles removed any other way (leaving the run, say)
+    /// free their share too.
+    pub fn recount(&mut self, live: usize) {
+        self.live = live;
+    }
+}
+
 #[derive(Resource, Default)]
 pub struct GameAssets {
     pub font_primary: Handle<Font>,
     pub font_numbers: Handle<Font>,
     pub player: Handle<Image>,
-    pub enemy: Handle<Image>,
     pub background: Handle<Image>,
     pub trail_segment: Handle<Image>,
     pub wave_projectile: Handle<Image>,
@@ -230,4 +412,301 @@ pub struct GameAssets {
     pub powerup_shield: Handle<Image>,
     pub powerup_accuracy: Handle<Image>,
     pub powerup_waveblast: Handle<Image>,
+    pub enemy_catalog: Handle<EnemyCatalog>,
+}
+
+/// Where each run's seed comes from.
+#[derive(Resource, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SeedSource {
+    /// A fresh seed every run.
+    #[default]
+    Random,
+    /// The same seed every run, as picked by the player or a test.
+    Fixed(u64),
+    /// One seed per UTC day shared by every player, written as `YYYYMMDD`.
+    Daily,
+}
+
+impl SeedSource {
+    /// The seed for a run starting `now` seconds after the Unix epoch.
+    pub fn resolve(self, now: u64) -> u64 {
+        match self {
+            Self::Random => rand::random(),
+            Self::Fixed(seed) => seed,
+            Self::Daily => {
+                let (year, month, day) = civil_date(now / 86_400);
+                year * 10_000 + month * 100 + day
+            }
+        }
+    }
+}
+
+/// Gregorian year, month and day of the given day since the Unix epoch.
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's `civil_from_days`, restricted to dates after 1970.
+    let shifted = days + 719_468;
+    let era = shifted / 146_097;
+    let day_of_era = shifted % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1_460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Every roll a run makes, drawn from streams derived from one seed so the
+/// same seed plays out the same run. Each stream only advances for its own
+/// kind of roll; an extra particle never shifts the next spawn.
+#[derive(Resource, Debug)]
+pub struct RunRng {
+    pub seed: u64,
+    /// Wave plans, spawn positions, enemy and boss behaviour.
+    pub spawning: StdRng,
+    /// Power-up drop chances and kinds.
+    pub drops: StdRng,
+    /// Particles, screen shake and anything else purely visual.
+    pub cosmetic: StdRng,
+}
+
+impl RunRng {
+    pub fn new(seed: u64) -> Self {
+        let stream =
+            |index: u64| StdRng::seed_from_u64(seed ^ index.wrapping_mul(0x9e37_79b9_7f4a_7c15));
+        Self {
+            seed,
+            spawning: stream(1),
+            drops: stream(2),
+            cosmetic: stream(3),
+        }
+    }
+}
+
+impl Default for RunRng {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::BASE_SCORE;
+    use rand::Rng;
+
+    const DAY: u64 = 86_400;
+
+    #[test]
+    fn civil_dates() {
+        assert_eq!(civil_date(0), (1970, 1, 1));
+        assert_eq!(civil_date(1_095), (1972, 12, 31));
+        assert_eq!(civil_date(11_016), (2000, 2, 29));
+        assert_eq!(civil_date(19_782), (2024, 2, 29));
+        // 2100 is not a leap year.
+        assert_eq!(civil_date(47_540), (2100, 2, 28));
+        assert_eq!(civil_date(47_541), (2100, 3, 1));
+    }
+
+    #[test]
+    fn daily_seed_is_the_utc_date() {
+        assert_eq!(SeedSource::Daily.resolve(0), 19_700_101);
+        assert_eq!(SeedSource::Daily.resolve(19_782 * DAY), 20_240_229);
+        assert_eq!(SeedSource::Daily.resolve(19_783 * DAY - 1), 20_240_229);
+        assert_eq!(SeedSource::Daily.resolve(19_783 * DAY), 20_240_301);
+    }
+
+    #[test]
+    fn fixed_seed_ignores_the_clock() {
+        assert_eq!(SeedSource::Fixed(42).resolve(0), 42);
+        assert_eq!(SeedSource::Fixed(42).resolve(19_782 * DAY), 42);
+    }
+
+    #[test]
+    fn gameplay_streams_ignore_cosmetic_draws() {
+        let mut quiet = RunRng::new(7);
+        let mut busy = RunRng::new(7);
+        for _ in 0..1_000 {
+            busy.cosmetic.gen::<u64>();
+        }
+
+        for _ in 0..100 {
+            assert_eq!(quiet.spawning.gen::<u64>(), busy.spawning.gen::<u64>());
+            assert_eq!(quiet.drops.gen::<u64>(), busy.drops.gen::<u64>());
+        }
+    }
+
+    #[test]
+    fn streams_differ_from_each_other_and_across_seeds() {
+        let mut rng = RunRng::new(7);
+        let spawning: u64 = rng.spawning.gen();
+        let drops: u64 = rng.drops.gen();
+        let cosmetic: u64 = rng.cosmetic.gen();
+        assert_ne!(spawning, drops);
+        assert_ne!(spawning, cosmetic);
+        assert_ne!(drops, cosmetic);
+
+        assert_ne!(spawning, RunRng::new(8).spawning.gen::<u64>());
+    }
+
+    fn kills(combo: &mut ComboState, count: u32) {
+        for _ in 0..count {
+            combo.register_kill();
+            combo.tick(COMBO_WINDOW * 0.5);
+        }
+    }
+
+    #[test]
+    fn kills_inside_the_window_grow_the_chain() {
+        let mut combo = ComboState::default();
+        assert_eq!(combo.register_kill(), 1);
+        assert!(!combo.is_active());
+        assert_eq!(combo.apply(BASE_SCORE), BASE_SCORE);
+
+        combo.tick(COMBO_WINDOW * 0.9);
+        assert_eq!(combo.register_kill(), 2);
+        assert!(combo.is_active());
+        assert_eq!(combo.multiplier, 1.0 + COMBO_MULTIPLIER_STEP);
+        assert_eq!(combo.window, COMBO_WINDOW);
+    }
+
+    #[test]
+    fn multiplier_stops_at_the_cap() {
+        let mut combo = ComboState::default();
+        kills(&mut combo, 100);
+
+        assert_eq!(combo.count, 100);
+        assert_eq!(combo.multiplier, COMBO_MAX_MULTIPLIER);
+        assert_eq!(
+            combo.apply(BASE_SCORE),
+            (BASE_SCORE as f32 * COMBO_MAX_MULTIPLIER) as u32
+        );
+    }
+
+    #[test]
+    fn chain_expires_when_the_window_runs_out() {
+        let mut combo = ComboState::default();
+        kills(&mut combo, 3);
+        combo.tick(COMBO_WINDOW);
+
+        assert_eq!(combo.count, 0);
+        assert_eq!(combo.multiplier, 1.0);
+        assert_eq!(combo.peak, 3);
+        // The next kill starts over.
+        assert_eq!(combo.register_kill(), 1);
+    }
+
+    #[test]
+    fn hits_break_the_chain_but_keep_the_peak() {
+        let mut combo = ComboState::default();
+        kills(&mut combo, 6);
+        combo.break_chain();
+
+        assert_eq!(combo.count, 0);
+        assert_eq!(combo.multiplier, 1.0);
+        assert_eq!(combo.window, 0.0);
+        assert_eq!(combo.peak, 6);
+        assert_eq!(combo.register_kill(), 1);
+
+        combo.reset();
+        assert_eq!(combo.peak, 0);
+    }
+
+    #[test]
+    fn milestones_fire_on_exactly_their_counts() {
+        let mut combo = ComboState::default();
+        let mut reached = Vec::new();
+        for _ in 0..COMBO_MILESTONES[COMBO_MILESTONES.len() - 1] + 5 {
+            combo.register_kill();
+            if combo.at_milestone() {
+                reached.push(combo.count);
+            }
+        }
+
+        assert_eq!(reached, COMBO_MILESTONES);
+    }
+
+    fn record(score: u32) -> RunRecord {
+        RunRecord {
+            timestamp: u64::from(score),
+            score,
+            seed: None,
+            stats: RunStats {
+                peak_combo: score,
+                ..RunStats::default()
+            },
+        }
+    }
+
+    #[test]
+    fn history_keeps_the_newest_runs_up_to_the_limit() {
+        let mut history = RunHistory::default();
+        let runs = RUN_HISTORY_LIMIT as u32 + 5;
+        for score in 1..=runs {
+            history.push(record(score));
+        }
+
+        // Oldest dropped first; the rest stay in the order they were played,
+        // newest last.
+        let scores: Vec<u32> = history.records.iter().map(|record| record.score).collect();
+        let kept: Vec<u32> = (6..=runs).collect();
+        assert_eq!(scores, kept);
+        assert_eq!(history.best().map(|record| record.score), Some(runs));
+    }
+
+    #[test]
+    fn best_run_comes_from_the_top_record() {
+        let mut history = RunHistory::default();
+        for score in [40, 90, 15] {
+            history.push(record(score));
+        }
+
+        let best = BestRun::from_save(&history, 0);
+        assert_eq!(best.score, 90);
+        assert_eq!(best.stats.peak_combo, 90);
+    }
+
+    #[test]
+    fn stored_best_score_outlives_the_history() {
+        let mut history = RunHistory::default();
+        history.push(record(40));
+
+        // The record-setting run has since dropped out of the history.
+        let best = BestRun::from_save(&history, 500);
+        assert_eq!(best.score, 500);
+        assert_eq!(best.stats.peak_combo, 40);
+
+        let fresh = BestRun::from_save(&RunHistory::default(), 75);
+        assert_eq!(fresh.score, 75);
+        assert_eq!(fresh.stats, RunStats::default());
+    }
+
+    #[test]
+    fn bursts_are_trimmed_at_the_budget() {
+        let mut budget = ParticleBudget::default();
+        assert_eq!(budget.reserve(PARTICLE_BUDGET - 5), PARTICLE_BUDGET - 5);
+        assert_eq!(budget.reserve(12), 5);
+        assert_eq!(budget.reserve(1), 0);
+        assert_eq!(budget.live, PARTICLE_BUDGET);
+    }
+
+    #[test]
+    fn budget_recovers_as_particles_expire() {
+        let mut budget = ParticleBudget::default();
+        budget.reserve(PARTICLE_BUDGET * 2);
+        assert_eq!(budget.reserve(10), 0);
+
+        // A frame later, 30 of them have run out their lifetime.
+        budget.recount(PARTICLE_BUDGET - 30);
+        assert_eq!(budget.reserve(50), 30);
+
+        // Leaving the run clears every particle at once.
+        budget.recount(0);
+        assert_eq!(budget.reserve(50), 50);
+    }
 }
@@ -1,5 +1,4 @@
 use crate::resources::PurchasedUpgrades;
-use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
@@ -17,7 +16,7 @@ pub enum PlayerColor {
     Purple,
 }
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Serialize, Deserialize)]
 pub enum UpgradeType {
     MovementSpeed,
     MaxHealth,
@@ -0,0 +1,22 @@
+use bevy::prelude::*;
+
+/// Top-level flow of the app.
+#[derive(States, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+pub enum AppState {
+    /// Assets and saved progress are being primed; nothing is shown yet.
+    #[default]
+    Loading,
+    Title,
+    InGame,
+    GameOver,
+}
+
+/// What the run is doing while `AppState::InGame` is active.
+#[derive(SubStates, Default, Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[source(AppState = AppState::InGame)]
+pub enum GameState {
+    #[default]
+    Running,
+    Paused,
+    Shop,
+}
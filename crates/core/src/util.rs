@@ -1,4 +1,6 @@
+use crate::constants::SIMULATION_HZ;
 use bevy::prelude::*;
+use bevy::utils::SystemTime;
 
 pub fn clamp_to_bounds(position: &mut Vec2, bounds: Vec2) {
     let half = bounds * 0.5;
@@ -6,6 +8,29 @@ pub fn clamp_to_bounds(position: &mut Vec2, bounds: Vec2) {
     position.y = position.y.clamp(-half.y, half.y);
 }
 
+/// Squared distance from `point` to the closest point on the segment `a`-`b`.
+pub fn distance_squared_to_segment(point: Vec2, a: Vec2, b: Vec2) -> f32 {
+    let along = b - a;
+    let length_squared = along.length_squared();
+    if length_squared <= f32::EPSILON {
+        return point.distance_squared(a);
+    }
+    let t = ((point - a).dot(along) / length_squared).clamp(0.0, 1.0);
+    point.distance_squared(a + along * t)
+}
+
+/// What's left after `delta` seconds of something that keeps `retain` of
+/// itself every simulation step.
+pub fn decay(retain: f32, delta: f32) -> f32 {
+    retain.clamp(0.0, 1.0).powf(delta * SIMULATION_HZ as f32)
+}
+
+/// Lerp factor covering over `delta` seconds what lerping by `rate` once per
+/// simulation step would.
+pub fn smoothing(rate: f32, delta: f32) -> f32 {
+    1.0 - decay(1.0 - rate, delta)
+}
+
 pub fn screen_to_world(
     camera: &Camera,
     camera_transform: &GlobalTransform,
@@ -15,3 +40,45 @@ pub fn screen_to_world(
         .viewport_to_world(camera_transform, cursor)
         .map(|ray| ray.origin.truncate())
 }
+
+/// Wall-clock seconds since the Unix epoch; works on wasm as well as native.
+pub fn unix_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(SystemTime::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DT: f32 = 1.0 / 45.0;
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-5
+    }
+
+    #[test]
+    fn decay_is_the_same_in_one_step_or_two() {
+        for retain in [0.0, 0.5, 0.9, 0.995, 1.0] {
+            let once = decay(retain, DT);
+            let twice = decay(retain, DT / 2.0) * decay(retain, DT / 2.0);
+            assert!(close(once, twice), "retain {retain}: {once} vs {twice}");
+        }
+    }
+
+    #[test]
+    fn smoothing_is_the_same_in_one_step_or_two() {
+        let (start, target) = (Vec2::new(-40.0, 10.0), Vec2::new(25.0, -60.0));
+        for rate in [0.0, 0.1, 0.35, 1.0] {
+            let once = start.lerp(target, smoothing(rate, DT));
+            let half = smoothing(rate, DT / 2.0);
+            let twice = start.lerp(target, half).lerp(target, half);
+            assert!(
+                once.abs_diff_eq(twice, 1e-3),
+                "rate {rate}: {once} vs {twice}"
+            );
+        }
+    }
+}
@@ -0,0 +1,194 @@
+use crate::constants::{DIRECTOR_FIRST_LULL, DIRECTOR_GROUP_SPACING, DIRECTOR_PEAK_EVERY};
+use crate::enemies::EnemyCatalog;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+/// How a spawn group is laid out when it arrives.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Formation {
+    /// Each enemy arrives from its own direction.
+    #[default]
+    Scatter,
+    /// A tight knot from one direction.
+    Cluster,
+    /// A wall side by side, facing the player.
+    Line,
+    /// Evenly spaced all the way around the player.
+    Ring,
+}
+
+/// `count` enemies of one archetype, released `at` seconds into a wave.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnGroup {
+    pub at: f32,
+    pub archetype: String,
+    pub count: u32,
+    #[serde(default)]
+    pub formation: Formation,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct WavePlan {
+    /// Sorted by `at`.
+    pub groups: Vec<SpawnGroup>,
+    /// Peaks are denser and followed by a longer lull.
+    #[serde(default)]
+    pub peak: bool,
+}
+
+impl WavePlan {
+    /// Builds wave `wave` from the catalog's spawn table. Group sizes grow
+    /// with `elapsed` run time; every `DIRECTOR_PEAK_EVERY`th wave is a peak
+    /// that tightens the spacing and closes with a ring.
+    pub fn generate(wave: u32, elapsed: f32, catalog: &EnemyCatalog, rng: &mut impl Rng) -> Self {
+        let peak = wave.is_multiple_of(DIRECTOR_PEAK_EVERY);
+        let intensity = 1.0 + elapsed / 90.0;
+        let group_count = 3 + wave.min(15) / 3 + u32::from(peak) * 2;
+        let spacing = if peak {
+            DIRECTOR_GROUP_SPACING * 0.6
+        } else {
+            DIRECTOR_GROUP_SPACING
+        };
+
+        let groups = (0..group_count)
+            .filter_map(|index| {
+                let at = index as f32 * spacing;
+                let archetype = catalog.pick(elapsed + at, rng.gen())?;
+                let last = index + 1 == group_count;
+                let formation = if peak && last {
+                    Formation::Ring
+                } else {
+                    match rng.gen_range(0..3) {
+                        0 => Formation::Scatter,
+                        1 => Formation::Cluster,
+                        _ => Formation::Line,
+                    }
+                };
+                let base = match formation {
+                    Formation::Ring => 8.0,
+                    _ => rng.gen_range(2.0..4.0),
+                };
+                Some(SpawnGroup {
+                    at,
+                    archetype: catalog.archetypes[archetype].id.clone(),
+                    count: (base * intensity).round().max(1.0) as u32,
+                    formation,
+                })
+            })
+            .collect();
+
+        Self { groups, peak }
+    }
+}
+
+/// Paces enemy arrivals as a sequence of waves separated by lulls. Scripted
+/// waves from the catalog run first, then waves are generated.
+#[derive(Resource, Debug)]
+pub struct WaveDirector {
+    /// The wave in progress, or the last one finished during a lull; 0
+    /// before the first wave.
+    pub wave: u32,
+    pub plan: WavePlan,
+    /// How many of `plan.groups` have been released.
+    pub released: usize,
+    /// Seconds since the current wave started.
+    pub wave_time: f32,
+    /// Seconds of quiet left before the next wave.
+    pub lull: f32,
+    /// A boss wave doesn't end until its boss is dead.
+    pub boss_alive: bool,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            wave: 0,
+            plan: WavePlan::default(),
+            released: 0,
+            wave_time: 0.0,
+            lull: DIRECTOR_FIRST_LULL,
+            boss_alive: false,
+        }
+    }
+}
+
+impl WaveDirector {
+    pub fn in_lull(&self) -> bool {
+        self.lull > 0.0
+    }
+
+    pub fn start_wave(&mut self, plan: WavePlan) {
+        self.wave += 1;
+        self.plan = plan;
+        self.released = 0;
+        self.wave_time = 0.0;
+        self.lull = 0.0;
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.released >= self.plan.groups.len() && !self.boss_alive
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enemies::tests::shipped_catalog;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    fn generate(seed: u64, wave: u32, elapsed: f32) -> WavePlan {
+        WavePlan::generate(
+            wave,
+            elapsed,
+            &shipped_catalog(),
+            &mut StdRng::seed_from_u64(seed),
+        )
+    }
+
+    #[test]
+    fn same_seed_same_waves() {
+        for wave in 1..=20 {
+            let elapsed = wave as f32 * 20.0;
+            assert_eq!(generate(3, wave, elapsed), generate(3, wave, elapsed));
+        }
+        assert_ne!(generate(3, 7, 140.0), generate(4, 7, 140.0));
+    }
+
+    #[test]
+    fn generated_waves_are_sorted_and_known() {
+        let catalog = shipped_catalog();
+        for wave in 1..=20 {
+            let plan = generate(wave.into(), wave, wave as f32 * 20.0);
+            assert!(!plan.groups.is_empty());
+            assert!(plan.groups.windows(2).all(|pair| pair[0].at <= pair[1].at));
+            for group in &plan.groups {
+                assert!(catalog.index_of(&group.archetype).is_some());
+                assert!(group.count >= 1);
+            }
+        }
+    }
+
+    #[test]
+    fn peaks_fall_on_their_interval_and_close_with_a_ring() {
+        for wave in 1..=20 {
+            let plan = generate(9, wave, 60.0);
+            let peak = wave % DIRECTOR_PEAK_EVERY == 0;
+            assert_eq!(plan.peak, peak, "wave {wave}");
+            let last = plan.groups.last().map(|group| group.formation);
+            assert_eq!(last == Some(Formation::Ring), peak, "wave {wave}");
+        }
+    }
+
+    #[test]
+    fn boss_waves_stay_open_until_the_boss_dies() {
+        let mut director = WaveDirector::default();
+        director.start_wave(WavePlan::default());
+        assert!(director.is_finished());
+
+        director.boss_alive = true;
+        assert!(!director.is_finished());
+    }
+}
@@ -0,0 +1,159 @@
+//! Cost of one frame's overlap tests, brute force against the spatial grid,
+//! at 50, 200 and 1000 enemies, with enemy shots and hazards in proportion.
+//! Run with `cargo bench -p threadweaver-gameplay`.
+
+use bevy::prelude::*;
+use rand::prelude::*;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+use threadweaver_core::constants::{PLAYER_RADIUS, TRAIL_WIDTH, WAVE_PROJECTILE_SIZE};
+use threadweaver_core::hitbox::Shape;
+use threadweaver_gameplay::spatial::SpatialGrid;
+
+const TRAIL_SEGMENTS: usize = 200;
+const WAVE_PROJECTILES: usize = 30;
+const FIELD: f32 = 1600.0;
+const ENEMY_RADIUS: f32 = 22.0;
+/// Enemies per shot and per hazard on the field.
+const ENEMIES_PER_SHOT: usize = 4;
+const ENEMIES_PER_HAZARD: usize = 10;
+const SHOT_RADIUS: f32 = 7.0;
+const HAZARD_RADIUS: f32 = 60.0;
+
+struct Frame {
+    enemies: Vec<Shape>,
+    segments: Vec<Shape>,
+    projectiles: Vec<Shape>,
+    shots: Vec<Shape>,
+    hazards: Vec<Shape>,
+}
+
+/// One grid per kind of thing indexed, as the game keeps them.
+#[derive(Default)]
+struct Grids {
+    enemies: SpatialGrid<()>,
+    shots: SpatialGrid<()>,
+    hazards: SpatialGrid<()>,
+}
+
+impl Frame {
+    fn new(enemy_count: usize, rng: &mut StdRng) -> Self {
+        let mut point = || Vec2::new(rng.gen_range(-0.5..0.5), rng.gen_range(-0.5..0.5)) * FIELD;
+        let enemies = (0..enemy_count)
+            .map(|_| Shape::circle(point(), ENEMY_RADIUS))
+            .collect();
+        let half = Vec2::X * (WAVE_PROJECTILE_SIZE.x - WAVE_PROJECTILE_SIZE.y) * 0.5;
+        let projectiles = (0..WAVE_PROJECTILES)
+            .map(|_| {
+                let center = point();
+                Shape::capsule(center - half, center + half, WAVE_PROJECTILE_SIZE.y * 0.5)
+            })
+            .collect();
+        let shots = (0..enemy_count / ENEMIES_PER_SHOT)
+            .map(|_| Shape::circle(point(), SHOT_RADIUS))
+            .collect();
+        let hazards = (0..enemy_count / ENEMIES_PER_HAZARD)
+            .map(|_| Shape::circle(point(), HAZARD_RADIUS))
+            .collect();
+        // A looping thread around the player, ~25 units between samples.
+        let spine: Vec<Vec2> = (0..=TRAIL_SEGMENTS)
+            .map(|index| {
+                let angle = index as f32 * 0.08;
+                Vec2::from_angle(angle) * (200.0 + index as f32)
+            })
+            .collect();
+        let segments = spine
+            .windows(2)
+            .map(|pair| Shape::capsule(pair[0], pair[1], TRAIL_WIDTH * 0.5))
+            .collect();
+        Self {
+            enemies,
+            segments,
+            projectiles,
+            shots,
+            hazards,
+        }
+    }
+}
+
+fn brute_force(frame: &Frame) -> usize {
+    let player = Shape::circle(Vec2::ZERO, PLAYER_RADIUS);
+    let mut hits = 0;
+    for enemy in &frame.enemies {
+        if frame.segments.iter().any(|segment| segment.overlaps(enemy)) {
+            hits += 1;
+        }
+        if player.overlaps(enemy) {
+            hits += 1;
+        }
+        hits += frame
+            .projectiles
+            .iter()
+            .filter(|projectile| projectile.overlaps(enemy))
+            .count();
+    }
+    // Enemy fire is only ever tested against the player.
+    for shape in frame.shots.iter().chain(&frame.hazards) {
+        if player.overlaps(shape) {
+            hits += 1;
+        }
+    }
+    hits
+}
+
+fn index(grid: &mut SpatialGrid<()>, shapes: &[Shape]) {
+    grid.clear();
+    for (index, &shape) in shapes.iter().enumerate() {
+        grid.insert(Entity::from_raw(index as u32), shape);
+    }
+}
+
+fn grid(frame: &Frame, grids: &mut Grids) -> usize {
+    index(&mut grids.enemies, &frame.enemies);
+    index(&mut grids.shots, &frame.shots);
+    index(&mut grids.hazards, &frame.hazards);
+
+    let player = Shape::circle(Vec2::ZERO, PLAYER_RADIUS);
+    let mut hits = 0;
+    for &segment in &frame.segments {
+        hits += grids.enemies.query(segment).count();
+    }
+    hits += grids.enemies.query(player).count();
+    for &projectile in &frame.projectiles {
+        hits += grids.enemies.query(projectile).count();
+    }
+    hits += grids.shots.query(player).count();
+    hits += grids.hazards.query(player).count();
+    hits
+}
+
+fn measure(mut run: impl FnMut() -> usize) -> Duration {
+    // Warm up, then average over as many runs as fit in half a second.
+    for _ in 0..10 {
+        black_box(run());
+    }
+    let start = Instant::now();
+    let mut runs = 0;
+    while start.elapsed() < Duration::from_millis(500) {
+        black_box(run());
+        runs += 1;
+    }
+    start.elapsed() / runs
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut grids = Grids::default();
+    println!(
+        "{TRAIL_SEGMENTS} trail segments, {WAVE_PROJECTILES} wave projectiles, \
+         enemies across {FIELD}x{FIELD} with a shot per {ENEMIES_PER_SHOT} \
+         and a hazard per {ENEMIES_PER_HAZARD}"
+    );
+    println!("{:>8} {:>14} {:>14}", "enemies", "brute force", "grid");
+    for enemy_count in [50, 200, 1000] {
+        let frame = Frame::new(enemy_count, &mut rng);
+        let brute = measure(|| brute_force(&frame));
+        let indexed = measure(|| grid(&frame, &mut grids));
+        println!("{enemy_count:>8} {brute:>14.2?} {indexed:>14.2?}");
+    }
+}
@@ -1,25 +1,53 @@
-use bevy::input::gamepad::{GamepadAxisType, GamepadEvent};
+mod replay;
+pub mod spatial;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext, LoadState};
+use bevy::ecs::system::SystemParam;
+use bevy::input::gamepad::{GamepadAxisType, GamepadButtonType, GamepadEvent};
 use bevy::input::touch::{TouchInput, TouchPhase};
+use bevy::input::InputSystem;
 use bevy::prelude::*;
+use bevy::render::mesh::{Indices, PrimitiveTopology, VertexAttributeValues};
+use bevy::render::render_asset::RenderAssetUsages;
+use bevy::render::view::NoFrustumCulling;
+use bevy::sprite::{MaterialMesh2dBundle, Mesh2dHandle};
 use bevy::window::PrimaryWindow;
 use rand::prelude::*;
 
 #[cfg(target_arch = "wasm32")]
 use getrandom as _;
+use replay::{QueuedReplay, RunTape};
+use spatial::SpatialGrid;
 use std::f32::consts::TAU;
-use std::time::Duration;
+use std::ops::Range;
 use threadweaver_core::components::*;
 use threadweaver_core::constants::*;
+use threadweaver_core::enemies::*;
+use threadweaver_core::hitbox::{Hitbox, Shape};
 use threadweaver_core::resources::*;
 use threadweaver_core::shop::{UpgradeType, SHOP_ITEMS};
-use threadweaver_core::util::{clamp_to_bounds, screen_to_world};
-use threadweaver_platform::{load_currency, load_upgrades, save_currency, save_upgrades};
+use threadweaver_core::state::{AppState, GameState};
+use threadweaver_core::util::{clamp_to_bounds, decay, screen_to_world, smoothing, unix_timestamp};
+use threadweaver_core::waves::*;
+use threadweaver_platform::{ReplayLoader, SaveStore};
 
 pub struct GameplayPlugin;
 
 impl Plugin for GameplayPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(RunState::new())
+        if !app.world().contains_resource::<SaveStore>() {
+            app.insert_resource(SaveStore::platform_default());
+        }
+
+        app.init_resource::<Arena>()
+            .insert_resource(Time::<Fixed>::from_hz(SIMULATION_HZ))
+            .init_asset::<EnemyCatalog>()
+            .register_asset_loader(EnemyCatalogLoader)
+            .init_state::<AppState>()
+            .add_sub_state::<GameState>()
+            .enable_state_scoped_entities::<AppState>()
+            .enable_state_scoped_entities::<GameState>()
             .insert_resource(PointerTarget::default())
             .insert_resource(PlayerStats::default())
             .insert_resource(PlayerHealth::with_max(PLAYER_MAX_HEALTH))
@@ -28,65 +56,217 @@ impl Plugin for GameplayPlugin {
                 duration: SHIELD_DURATION,
             })
             .insert_resource(Score::default())
-            .insert_resource(Currency {
-                balance: load_currency(),
-            })
+            .insert_resource(ComboState::default())
+            .insert_resource(RunStats::default())
+            .insert_resource(BestRun::default())
+            .insert_resource(RunSummary::default())
+            .insert_resource(RunHistory::default())
+            .insert_resource(Currency::default())
             .insert_resource(PurchasedUpgrades::default())
             .insert_resource(ShopState::default())
-            .insert_resource(EnemySpawnTimer::new(ENEMY_SPAWN_INTERVAL_START))
+            .insert_resource(WaveDirector::default())
+            .insert_resource(ParticleBudget::default())
+            .init_resource::<SeedSource>()
+            .init_resource::<RunRng>()
+            .init_resource::<WeaponSwitch>()
+            .init_resource::<RunTape>()
+            .init_resource::<SpatialGrid<Enemy>>()
+            .init_resource::<SpatialGrid<PowerUp>>()
+            .init_resource::<SpatialGrid<EnemyProjectile>>()
+            .init_resource::<SpatialGrid<Hazard>>()
             .insert_resource(TrailSpawnTimer {
                 timer: Timer::from_seconds(TRAIL_SPAWN_INTERVAL, TimerMode::Repeating),
             })
             .add_event::<PlayerHitEvent>()
             .add_event::<ShopPurchaseEvent>()
+            .add_event::<ComboMilestoneEvent>()
+            .add_event::<WaveStartedEvent>()
+            .add_event::<DamageEvent>()
+            .add_event::<EnemyKilled>()
             .add_systems(Startup, setup_scene)
-            .add_systems(PostStartup, prime_persistence)
             .add_systems(
                 Update,
                 (
+                    resolve_enemy_catalog.run_if(not(resource_exists::<EnemyCatalog>)),
+                    replay::load_requested_replay.run_if(
+                        resource_exists::<EnemyCatalog>.and_then(resource_exists::<ReplayLoader>),
+                    ),
+                    prime_persistence.run_if(
+                        resource_exists::<EnemyCatalog>
+                            .and_then(not(resource_exists::<ReplayLoader>)),
+                    ),
+                )
+                    .chain()
+                    .run_if(in_state(AppState::Loading)),
+            )
+            .add_systems(
+                Update,
+                rebuild_arena_visuals.run_if(resource_changed::<Arena>),
+            )
+            .add_systems(
+                Update,
+                replay::start_queued_replay
+                    .run_if(in_state(AppState::Title).and_then(resource_exists::<QueuedReplay>)),
+            )
+            .add_systems(
+                OnEnter(AppState::InGame),
+                (start_run, replay::begin_tape).chain(),
+            )
+            .add_systems(
+                OnExit(AppState::InGame),
+                (finish_run, replay::end_tape).chain(),
+            )
+            .add_systems(
+                PreUpdate,
+                (
+                    read_pointer_input,
+                    read_touch_input,
+                    read_gamepad_input,
+                    read_weapon_switch,
+                )
+                    .chain()
+                    .after(InputSystem)
+                    .run_if(in_state(GameState::Running).and_then(not(replay::replaying))),
+            )
+            // Everything that moves, ages or collides steps at a fixed rate
+            // so a run plays the same at any frame rate.
+            .add_systems(FixedFirst, restore_simulated_translations)
+            .add_systems(
+                FixedUpdate,
+                (
+                    (replay::run_tape, apply_weapon_switch).chain(),
                     (
-                        read_pointer_input,
-                        read_touch_input,
-                        read_gamepad_input,
                         move_player,
-                        spawn_trail_segments,
-                        update_trail_segments,
-                        spawn_enemies,
+                        record_trail,
+                        age_trail,
+                        run_wave_director,
                         move_enemies,
+                        update_bosses,
+                        fire_enemy_attacks,
+                        index_enemies,
                         resolve_trail_hits,
+                        tick_combo,
+                        tick_run_stats,
                         resolve_player_collisions,
+                        update_enemy_projectiles,
+                        tick_hazards,
+                        index_enemy_fire,
+                        resolve_enemy_fire,
                     )
                         .chain(),
                     (
                         tick_powerups,
+                        index_powerups,
                         apply_powerup_pickups,
                         update_shield_state,
                         advance_wave_projectile_timer,
                         update_wave_projectiles,
-                        update_particles,
-                        apply_screen_shake,
+                        resolve_wave_hits,
                     )
                         .chain(),
+                    (apply_damage, handle_enemy_kills).chain(),
                     handle_player_hit_events,
-                    (
-                        apply_shop_purchases,
-                        persist_currency_changes,
-                        persist_upgrade_changes,
-                    )
-                        .chain(),
                 )
-                    .chain(),
+                    .chain()
+                    .run_if(in_state(GameState::Running).and_then(run_in_progress)),
+            )
+            .add_systems(FixedLast, record_simulated_translations)
+            .add_systems(
+                Update,
+                (
+                    interpolate_translations,
+                    draw_trail_ribbon,
+                    run_particle_emitters,
+                    update_particles,
+                    follow_player,
+                    apply_screen_shake,
+                    replay::end_finished_replay,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Running)),
+            )
+            .add_systems(
+                Update,
+                (
+                    apply_shop_purchases,
+                    // A replay plays on the recording's save, not the player's.
+                    (persist_currency_changes, persist_upgrade_changes)
+                        .run_if(not(replay::replaying)),
+                    persist_run_history,
+                    flush_saves,
+                )
+                    .chain()
+                    .run_if(not(in_state(AppState::Loading))),
             )
-            .add_systems(Update, reset_when_run_stops.after(handle_player_hit_events));
+            .add_systems(Last, flush_saves_on_exit.run_if(on_event::<AppExit>()));
     }
 }
 
-fn prime_persistence(
+struct EnemyCatalogLoader;
+
+impl AssetLoader for EnemyCatalogLoader {
+    type Asset = EnemyCatalog;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<EnemyCatalog, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(serde_json::from_slice(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemies.json"]
+    }
+}
+
+/// Copies the loaded enemy catalog into a resource once it's ready. A missing
+/// or malformed data file falls back to the built-in chaser so the game still
+/// starts.
+fn resolve_enemy_catalog(
     mut commands: Commands,
-    mut health: ResMut<PlayerHealth>,
-    mut shield: ResMut<ShieldState>,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    catalogs: Res<Assets<EnemyCatalog>>,
+) {
+    let catalog = match catalogs.get(&assets.enemy_catalog) {
+        Some(catalog) if !catalog.archetypes.is_empty() => catalog.clone(),
+        Some(_) => {
+            warn!("Enemy catalog defines no archetypes; using the built-in chaser");
+            EnemyCatalog::default()
+        }
+        None => match asset_server.get_load_state(&assets.enemy_catalog) {
+            Some(LoadState::Failed(error)) => {
+                warn!("Enemy catalog failed to load ({error}); using the built-in chaser");
+                EnemyCatalog::default()
+            }
+            _ => return,
+        },
+    };
+
+    let unknown = catalog.unknown_ids();
+    if !unknown.is_empty() {
+        warn!("Enemy catalog references undefined archetypes: {unknown:?}");
+    }
+    let non_bosses = catalog.non_boss_milestones();
+    if !non_bosses.is_empty() {
+        warn!("Boss waves name archetypes without a boss spec: {non_bosses:?}");
+    }
+    commands.insert_resource(catalog);
+}
+
+/// Sizes health and the shield from the upgrades owned, with health full and
+/// no shield up.
+fn apply_upgrades(
+    upgrades: &PurchasedUpgrades,
+    health: &mut PlayerHealth,
+    shield: &mut ShieldState,
 ) {
-    let upgrades = load_upgrades();
     let mut upgraded_health = PLAYER_MAX_HEALTH + upgrades.max_health_bonus();
     if upgraded_health == 0 {
         upgraded_health = PLAYER_MAX_HEALTH;
@@ -95,8 +275,39 @@ fn prime_persistence(
     health.current = upgraded_health;
     shield.duration = SHIELD_DURATION + upgrades.shield_duration_bonus();
     shield.remaining = 0.0;
+}
+
+#[allow(clippy::too_many_arguments)]
+fn prime_persistence(
+    mut commands: Commands,
+    mut store: ResMut<SaveStore>,
+    mut currency: ResMut<Currency>,
+    mut health: ResMut<PlayerHealth>,
+    mut shield: ResMut<ShieldState>,
+    mut score: ResMut<Score>,
+    mut best: ResMut<BestRun>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    // Web saves load asynchronously; stay on the loading state until they do.
+    if !store.poll_loaded() {
+        return;
+    }
+
+    let save = store.data();
+    currency.balance = save.currency;
+
+    let upgrades = save.upgrades.clone();
+    apply_upgrades(&upgrades, &mut health, &mut shield);
+
+    let history = RunHistory {
+        records: save.run_history.clone(),
+    };
+    *best = BestRun::from_save(&history, save.best_score);
+    score.best = best.score;
 
     commands.insert_resource(upgrades);
+    commands.insert_resource(history);
+    next_state.set(AppState::Title);
 }
 
 #[derive(Event)]
@@ -107,10 +318,48 @@ pub struct ShopPurchaseEvent {
     pub item: UpgradeType,
 }
 
-fn setup_scene(mut commands: Commands, asset_server: Res<AssetServer>) {
+/// Sent as the director releases the first group of a new wave.
+#[derive(Event)]
+pub struct WaveStartedEvent {
+    pub wave: u32,
+    pub peak: bool,
+    pub boss: bool,
+}
+
+/// Damage dealt to an enemy. Every damage source sends these rather than
+/// touching `EnemyHealth` itself.
+#[derive(Event)]
+pub struct DamageEvent {
+    pub target: Entity,
+    pub amount: f32,
+    pub source: KillSource,
+    /// Added to the target's knockback.
+    pub knockback: Vec2,
+}
+
+/// Sent once per enemy, on the hit that takes its health to zero.
+#[derive(Event)]
+pub struct EnemyKilled {
+    pub enemy: Entity,
+    pub position: Vec2,
+    pub archetype: usize,
+    pub source: KillSource,
+}
+
+/// Sent when the kill chain reaches one of `COMBO_MILESTONES`.
+#[derive(Event)]
+pub struct ComboMilestoneEvent {
+    pub count: u32,
+}
+
+fn setup_scene(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
     let background = asset_server.load("background_tile.png");
     let player_texture = asset_server.load("sprites/player.png");
-    let enemy_texture = asset_server.load("sprites/enemy_basic.png");
     let trail_texture = asset_server.load("sprites/effects/trail_segment.png");
     let wave_texture = asset_server.load("sprites/effects/wave_projectile.png");
     let currency_texture = asset_server.load("sprites/powerups/currency.png");
@@ -124,7 +373,6 @@ fn setup_scene(mut commands: Commands, asset_server: Res<AssetServer>) {
         font_primary: font_primary.clone(),
         font_numbers: font_primary.clone(),
         player: player_texture.clone(),
-        enemy: enemy_texture,
         background: background.clone(),
         trail_segment: trail_texture,
         wave_projectile: wave_texture,
@@ -133,44 +381,59 @@ fn setup_scene(mut commands: Commands, asset_server: Res<AssetServer>) {
         powerup_shield: shield_texture,
         powerup_accuracy: accuracy_texture,
         powerup_waveblast: waveblast_texture,
+        enemy_catalog: asset_server.load("data/enemies.json"),
     });
 
     commands.spawn((
         Camera2dBundle::default(),
         MainCamera,
+        CameraRig::default(),
         ScreenShake::default(),
     ));
 
-    // Spawn tiled background instead of single large sprite
-    spawn_background_tiles(&mut commands, background);
-
     commands.spawn((
         SpriteBundle {
             texture: player_texture,
             transform: Transform::from_xyz(0.0, 0.0, 1.0),
             sprite: Sprite {
-                custom_size: Some(Vec2::splat(48.0)),
+                custom_size: Some(Vec2::splat(PLAYER_SIZE)),
                 ..Default::default()
             },
             ..Default::default()
         },
         Player::default(),
+        Hitbox::Circle {
+            radius: PLAYER_RADIUS,
+        },
+        Interpolated::default(),
         PlayerVelocity::default(),
         Knockback::default(),
+        TrailHistory::default(),
+    ));
+
+    commands.spawn((
+        MaterialMesh2dBundle {
+            mesh: meshes
+                .add(ribbon_mesh(Vec::new(), Vec::new(), vec![0, 0, 0]))
+                .into(),
+            material: materials.add(ColorMaterial::default()),
+            transform: Transform::from_xyz(0.0, 0.0, 0.2),
+            visibility: Visibility::Hidden,
+            ..Default::default()
+        },
+        TrailRibbon,
+        // The mesh is rebuilt every frame, so its bounds are never current.
+        NoFrustumCulling,
     ));
 }
 
 fn read_pointer_input(
     mut events: EventReader<CursorMoved>,
+    arena: Res<Arena>,
     mut target: ResMut<PointerTarget>,
-    run_state: Res<RunState>,
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
     let Ok(window) = windows.get_single() else {
         return;
     };
@@ -180,28 +443,23 @@ fn read_pointer_input(
 
     for event in events.read() {
         if let Some(position) = screen_to_world(camera, transform, event.position) {
-            target.position = position;
-            clamp_to_bounds(&mut target.position, ARENA_BOUNDS);
+            target.position = arena.clamp(position);
         }
     }
 
     if window.cursor_position().is_none() {
         // keep pointer inside arena when cursor hidden
-        clamp_to_bounds(&mut target.position, ARENA_BOUNDS);
+        target.position = arena.clamp(target.position);
     }
 }
 
 fn read_touch_input(
     mut touch_events: EventReader<TouchInput>,
+    arena: Res<Arena>,
     mut target: ResMut<PointerTarget>,
-    run_state: Res<RunState>,
     windows: Query<&Window, With<PrimaryWindow>>,
     camera_q: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
     if windows.get_single().is_err() {
         return;
     }
@@ -218,24 +476,19 @@ fn read_touch_input(
             if target.position.distance_squared(position)
                 > TOUCH_DRAG_DEADZONE * TOUCH_DRAG_DEADZONE
             {
-                target.position = position;
-                clamp_to_bounds(&mut target.position, ARENA_BOUNDS);
+                target.position = arena.clamp(position);
             }
         }
     }
 
-    clamp_to_bounds(&mut target.position, ARENA_BOUNDS);
+    target.position = arena.clamp(target.position);
 }
 
 fn read_gamepad_input(
     mut events: EventReader<GamepadEvent>,
+    arena: Res<Arena>,
     mut target: ResMut<PointerTarget>,
-    run_state: Res<RunState>,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
     for event in events.read() {
         if let GamepadEvent::Axis(axis_event) = event {
             match axis_event.axis_type {
@@ -246,40 +499,90 @@ fn read_gamepad_input(
         }
     }
 
-    clamp_to_bounds(&mut target.position, ARENA_BOUNDS);
+    target.position = arena.clamp(target.position);
+}
+
+fn read_weapon_switch(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut switch: ResMut<WeaponSwitch>,
+) {
+    let mut requested = keys.just_pressed(KeyCode::Tab);
+    for event in gamepad_events.read() {
+        if let GamepadEvent::Button(button_event) = event {
+            if button_event.button_type == GamepadButtonType::West && button_event.value > 0.5 {
+                requested = true;
+            }
+        }
+    }
+
+    switch.requested |= requested;
+}
+
+fn apply_weapon_switch(mut switch: ResMut<WeaponSwitch>, mut query: Query<&mut Player>) {
+    if !std::mem::take(&mut switch.requested) {
+        return;
+    }
+
+    if let Ok(mut player) = query.get_single_mut() {
+        player.weapon = player.weapon.next();
+    }
+}
+
+/// The run is still being played: the player is alive and, in a replay,
+/// there is input left.
+fn run_in_progress(health: Res<PlayerHealth>, tape: Res<RunTape>) -> bool {
+    !health.is_dead() && !tape.is_finished()
 }
 
+#[allow(clippy::too_many_arguments)]
 fn move_player(
     time: Res<Time>,
+    arena: Res<Arena>,
     stats: Res<PlayerStats>,
     upgrades: Res<PurchasedUpgrades>,
     target: Res<PointerTarget>,
-    run_state: Res<RunState>,
+    shield: Res<ShieldState>,
+    hazard_grid: Res<SpatialGrid<Hazard>>,
+    hazards: Query<&Hazard>,
     mut query: Query<
         (
             &mut Transform,
             &mut PlayerVelocity,
             &mut Player,
             &mut Knockback,
+            &Hitbox,
         ),
         With<Player>,
     >,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
-    let Ok((mut transform, mut velocity, mut player, mut knockback)) = query.get_single_mut()
+    let Ok((mut transform, mut velocity, mut player, mut knockback, hitbox)) =
+        query.get_single_mut()
     else {
         return;
     };
     let mut current = transform.translation.truncate();
+    let body = hitbox.placed(&transform);
     let delta = target.position - current;
     let distance = delta.length();
 
-    let speed = stats.base_speed * upgrades.movement_speed_multiplier();
-    let accel = stats.acceleration;
-    let decel = stats.deceleration;
+    // The strongest slow field underfoot applies; a shield shrugs them off.
+    let slow = if shield.is_active() {
+        1.0
+    } else {
+        hazard_grid
+            .query(body)
+            .filter_map(|entry| hazards.get(entry.entity).ok())
+            .filter_map(|hazard| match hazard.effect {
+                HazardEffect::Slow { factor } if hazard.is_armed() => Some(factor),
+                _ => None,
+            })
+            .fold(1.0, f32::min)
+    };
+    let speed = stats.base_speed * upgrades.movement_speed_multiplier() * slow;
+    let dt = time.delta_seconds();
+    let accel = smoothing(stats.acceleration, dt);
+    let decel = smoothing(stats.deceleration, dt);
 
     if distance > 4.0 {
         let desired = delta.normalize() * speed;
@@ -289,202 +592,1220 @@ fn move_player(
     }
 
     velocity.current += knockback.velocity;
-    knockback.velocity *= 0.90; // damp knockback
+    knockback.velocity *= decay(PLAYER_KNOCKBACK_DAMPING, dt);
 
-    current += velocity.current * time.delta_seconds();
-    clamp_to_bounds(&mut current, ARENA_BOUNDS);
+    current += velocity.current * dt;
+    current = arena.clamp(current);
     transform.translation.x = current.x;
     transform.translation.y = current.y;
 
-    player.wave_cooldown = (player.wave_cooldown - time.delta_seconds()).max(0.0);
+    player.wave_cooldown = (player.wave_cooldown - dt).max(0.0);
 }
 
-fn spawn_trail_segments(
-    mut commands: Commands,
+/// Samples the player's position into their trail history while the trail
+/// weapon is equipped.
+fn record_trail(
     time: Res<Time>,
-    assets: Res<GameAssets>,
-    run_state: Res<RunState>,
     mut timer: ResMut<TrailSpawnTimer>,
     upgrades: Res<PurchasedUpgrades>,
-    query: Query<&Transform, With<Player>>,
+    mut query: Query<(&Transform, &Player, &mut TrailHistory)>,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
-    let Ok(transform) = query.get_single() else {
+    let Ok((transform, player, mut history)) = query.get_single_mut() else {
         return;
     };
+    if player.weapon != WeaponType::Trail {
+        return;
+    }
     if !timer.timer.tick(time.delta()).just_finished() {
         return;
     }
 
-    commands.spawn((
-        SpriteBundle {
-            texture: assets.trail_segment.clone(),
-            transform: Transform::from_xyz(transform.translation.x, transform.translation.y, 0.2),
-            sprite: Sprite {
-                custom_size: Some(Vec2::splat(12.0)),
-                ..Default::default()
-            },
-            ..Default::default()
-        },
-        TrailSegment {
-            remaining: TRAIL_LIFETIME,
-            damage: TRAIL_START_DAMAGE as f32 * upgrades.trail_damage_multiplier(),
-        },
-    ));
+    history.points.push_back(TrailPoint {
+        position: transform.translation.truncate(),
+        remaining: TRAIL_LIFETIME,
+        damage: TRAIL_START_DAMAGE as f32 * upgrades.trail_damage_multiplier(),
+    });
 }
 
-fn update_trail_segments(
-    mut commands: Commands,
-    time: Res<Time>,
-    mut query: Query<(Entity, &mut TrailSegment, &mut Transform, &mut Sprite)>,
+fn age_trail(time: Res<Time>, mut query: Query<&mut TrailHistory>) {
+    for mut history in &mut query {
+        for point in &mut history.points {
+            point.remaining -= time.delta_seconds();
+        }
+        while history
+            .points
+            .front()
+            .is_some_and(|point| point.remaining <= 0.0)
+        {
+            history.points.pop_front();
+        }
+    }
+}
+
+const TRAIL_COLOR: Color = Color::srgb(0.4, 0.9, 1.0);
+
+/// Rebuilds the thread as one triangle strip through the trail history. Each
+/// sample contributes an edge, centre, edge triple so the ribbon glows in
+/// the middle and fades out at its sides; width and alpha taper toward the
+/// oldest end.
+fn draw_trail_ribbon(
+    player: Query<(&Transform, &Player, &TrailHistory)>,
+    mut ribbons: Query<(&Mesh2dHandle, &mut Visibility), With<TrailRibbon>>,
+    mut meshes: ResMut<Assets<Mesh>>,
 ) {
-    for (entity, mut segment, mut transform, mut sprite) in &mut query {
-        segment.remaining -= time.delta_seconds();
-        if segment.remaining <= 0.0 {
-            commands.entity(entity).despawn_recursive();
-            continue;
+    let Ok((transform, player, history)) = player.get_single() else {
+        return;
+    };
+    let Ok((handle, mut visibility)) = ribbons.get_single_mut() else {
+        return;
+    };
+
+    let mut spine: Vec<(Vec2, f32)> = history
+        .points
+        .iter()
+        .map(|point| (point.position, point.remaining / TRAIL_LIFETIME))
+        .collect();
+    // Run the newest end right up to the player so the thread never lags.
+    if player.weapon == WeaponType::Trail && !spine.is_empty() {
+        spine.push((transform.translation.truncate(), 1.0));
+    }
+    if spine.len() < 2 {
+        *visibility = Visibility::Hidden;
+        return;
+    }
+    let Some(mesh) = meshes.get_mut(&handle.0) else {
+        return;
+    };
+    *visibility = Visibility::Inherited;
+
+    // Refill the ribbon's own buffers rather than building a new mesh, so
+    // their allocations carry over from frame to frame.
+    if let Some(VertexAttributeValues::Float32x3(positions)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_POSITION)
+    {
+        positions.clear();
+        let half_width = |t: f32| TRAIL_WIDTH * 0.5 * (0.3 + 0.7 * t.clamp(0.0, 1.0));
+        let mut normal = Vec2::Y;
+        for (index, &(position, t)) in spine.iter().enumerate() {
+            let before = spine[index.saturating_sub(1)].0;
+            let after = spine[(index + 1).min(spine.len() - 1)].0;
+            let tangent = (after - before).normalize_or_zero();
+            if tangent != Vec2::ZERO {
+                normal = tangent.perp();
+            }
+            for offset in [-half_width(t), 0.0, half_width(t)] {
+                let vertex = position + normal * offset;
+                positions.push([vertex.x, vertex.y, 0.0]);
+            }
         }
+    }
+
+    if let Some(VertexAttributeValues::Float32x4(colors)) =
+        mesh.attribute_mut(Mesh::ATTRIBUTE_COLOR)
+    {
+        colors.clear();
+        let color = TRAIL_COLOR.to_linear();
+        let edge = color.with_alpha(0.0).to_f32_array();
+        for &(_, t) in &spine {
+            let t = t.clamp(0.0, 1.0);
+            let centre = color.with_alpha(0.9 * t * t).to_f32_array();
+            colors.extend_from_slice(&[edge, centre, edge]);
+        }
+    }
 
-        let t = (segment.remaining / TRAIL_LIFETIME).clamp(0.0, 1.0);
-        sprite.color.set_alpha(0.15 + 0.8 * t);
-        transform.scale = Vec3::splat(0.8 + t * 0.3);
+    if let Some(Indices::U32(indices)) = mesh.indices_mut() {
+        indices.clear();
+        for index in 0..spine.len() as u32 - 1 {
+            let base = index * 3;
+            for side in 0..2 {
+                let a = base + side;
+                indices.extend_from_slice(&[a, a + 3, a + 1, a + 1, a + 3, a + 4]);
+            }
+        }
     }
 }
 
-fn spawn_enemies(
+fn ribbon_mesh(positions: Vec<[f32; 3]>, colors: Vec<[f32; 4]>, indices: Vec<u32>) -> Mesh {
+    // A lone degenerate triangle stands in for an empty ribbon, so the GPU
+    // buffers are never zero-sized.
+    let (positions, colors) = if positions.is_empty() {
+        (vec![[0.0; 3]; 3], vec![[0.0; 4]; 3])
+    } else {
+        (positions, colors)
+    };
+    Mesh::new(
+        PrimitiveTopology::TriangleList,
+        RenderAssetUsages::default(),
+    )
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_COLOR, colors)
+    .with_inserted_indices(Indices::U32(indices))
+}
+
+/// Starts waves once each lull runs out and releases their groups on
+/// schedule, holding back whatever would push past `ENEMY_CAP`.
+#[allow(clippy::too_many_arguments)]
+fn run_wave_director(
     mut commands: Commands,
     time: Res<Time>,
-    mut timer: ResMut<EnemySpawnTimer>,
-    run_state: Res<RunState>,
-    score: Res<Score>,
-    assets: Res<GameAssets>,
+    mut director: ResMut<WaveDirector>,
+    run_stats: Res<RunStats>,
+    asset_server: Res<AssetServer>,
+    catalog: Res<EnemyCatalog>,
+    arena: Res<Arena>,
     player_q: Query<&Transform, With<Player>>,
+    enemies: Query<(), With<Enemy>>,
+    mut wave_events: EventWriter<WaveStartedEvent>,
+    mut rng: ResMut<RunRng>,
 ) {
-    if !run_state.is_running() {
+    let Ok(player_transform) = player_q.get_single() else {
         return;
-    }
+    };
+    let player_pos = player_transform.translation.truncate();
+    let elapsed = run_stats.time_survived;
 
-    if !timer.timer.tick(time.delta()).just_finished() {
-        return;
+    if director.in_lull() {
+        director.lull -= time.delta_seconds();
+        if director.in_lull() {
+            return;
+        }
+
+        let wave = director.wave + 1;
+        let mut plan = match catalog.waves.get(director.wave as usize) {
+            Some(scripted) => scripted.clone(),
+            None => WavePlan::generate(wave, elapsed, &catalog, &mut rng.spawning),
+        };
+        let boss = catalog.boss_for_wave(wave);
+        if boss.is_some() {
+            // The boss is the wave; keep only a light escort.
+            plan.groups.truncate(BOSS_ESCORT_GROUPS);
+            plan.peak = true;
+        }
+        director.start_wave(plan);
+
+        if let Some(boss) = boss {
+            let position = arena_spawn_point(&arena, player_pos, &mut rng.spawning);
+            // Only a real boss holds the wave open until it dies.
+            director.boss_alive = spawn_boss(
+                &mut commands,
+                &asset_server,
+                &catalog,
+                boss,
+                position,
+                elapsed,
+                &mut rng.spawning,
+            );
+        }
+        wave_events.send(WaveStartedEvent {
+            wave,
+            peak: director.plan.peak,
+            boss: boss.is_some(),
+        });
+    } else {
+        director.wave_time += time.delta_seconds();
     }
 
-    let Ok(player_transform) = player_q.get_single() else {
-        return;
-    };
-    let base_pos = player_transform.translation.truncate();
+    let mut alive = enemies.iter().count();
+    while let Some(group) = director.plan.groups.get(director.released) {
+        if group.at > director.wave_time {
+            break;
+        }
+        let room = ENEMY_CAP.saturating_sub(alive) as u32;
+        if room == 0 {
+            break;
+        }
+
+        let count = group.count.min(room);
+        if let Some(archetype) = catalog.index_of(&group.archetype) {
+            for position in formation_positions(
+                group.formation,
+                count,
+                &arena,
+                player_pos,
+                &mut rng.spawning,
+            ) {
+                spawn_enemy(
+                    &mut commands,
+                    &asset_server,
+                    &catalog,
+                    archetype,
+                    position,
+                    elapsed,
+                    &mut rng.spawning,
+                );
+            }
+            alive += count as usize;
+        }
+
+        let released = director.released;
+        if count < group.count {
+            // Capped: the rest of the group arrives as room frees up.
+            director.plan.groups[released].count -= count;
+            break;
+        }
+        director.released += 1;
+    }
 
-    let mut rng = rand::thread_rng();
-    let angle = rng.gen_range(0.0..TAU);
-    let distance = ENEMY_SPAWN_DISTANCE + rng.gen_range(-100.0..100.0);
-    let spawn = base_pos + Vec2::from_angle(angle) * distance;
+    if director.is_finished() {
+        director.lull = if director.plan.peak {
+            DIRECTOR_PEAK_LULL
+        } else {
+            DIRECTOR_LULL
+        };
+    }
+}
 
-    let speed = ENEMY_BASE_SPEED + ENEMY_SPEED_INCREMENT * (score.current as f32 / 150.0);
-    let health = ENEMY_BASE_HEALTH as f32 + score.current as f32 / 500.0;
+/// Spawn points for `count` enemies arriving around `player` in `formation`,
+/// all inside the arena.
+fn formation_positions(
+    formation: Formation,
+    count: u32,
+    arena: &Arena,
+    player: Vec2,
+    rng: &mut impl Rng,
+) -> Vec<Vec2> {
+    let anchor = arena_spawn_point(arena, player, rng);
+    let inward = (player - anchor).normalize_or_zero();
+
+    (0..count)
+        .map(|index| {
+            let position = match formation {
+                Formation::Scatter => arena_spawn_point(arena, player, rng),
+                Formation::Cluster => {
+                    anchor + Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(0.0..60.0)
+                }
+                Formation::Line => {
+                    let offset = index as f32 - (count - 1) as f32 * 0.5;
+                    anchor + inward.perp() * offset * 56.0
+                }
+                Formation::Ring => {
+                    let angle = TAU * index as f32 / count as f32;
+                    player + Vec2::from_angle(angle) * ENEMY_SPAWN_DISTANCE
+                }
+            };
+            let position = arena.clamp(position);
+            // A wall can fold part of a formation back onto the player.
+            if position.distance(player) < ENEMY_SPAWN_MIN_DISTANCE {
+                arena_spawn_point(arena, player, rng)
+            } else {
+                position
+            }
+        })
+        .collect()
+}
 
-    commands.spawn((
+fn spawn_enemy(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    catalog: &EnemyCatalog,
+    index: usize,
+    position: Vec2,
+    elapsed: f32,
+    rng: &mut impl Rng,
+) -> Entity {
+    let archetype = &catalog.archetypes[index];
+    let [r, g, b] = archetype.tint;
+
+    let mut enemy = commands.spawn((
         SpriteBundle {
-            texture: assets.enemy.clone(),
-            transform: Transform::from_xyz(spawn.x, spawn.y, 0.3),
+            texture: asset_server.load(&archetype.sprite),
+            transform: Transform::from_xyz(position.x, position.y, 0.3),
             sprite: Sprite {
-                custom_size: Some(Vec2::new(48.0, 44.0)),
+                color: Color::srgb(r, g, b),
+                custom_size: Some(Vec2::from(archetype.size)),
                 ..Default::default()
             },
             ..Default::default()
         },
-        Enemy { speed },
-        EnemyHealth { current: health },
+        Enemy {
+            archetype: index,
+            speed: archetype.speed_at(elapsed),
+        },
+        EnemyHealth {
+            current: archetype.health.at(elapsed),
+        },
         EnemyVelocity::default(),
+        TrailHitCooldown::default(),
+        SteeringState {
+            circle_direction: if rng.gen() { 1.0 } else { -1.0 },
+            ..Default::default()
+        },
+        archetype.hitbox(),
+        Interpolated::default(),
         Knockback::default(),
+        StateScoped(AppState::InGame),
     ));
+    if let Some(attack) = archetype.attack {
+        // Stagger the first attack so a group doesn't fire in lockstep.
+        enemy.insert(AttackCooldown {
+            remaining: attack.cooldown() * rng.gen_range(0.5..1.0),
+        });
+    }
+    enemy.id()
+}
+
+/// Spawns archetype `index` as a boss. Returns whether it got a `Boss`; an
+/// archetype without a boss spec spawns as an ordinary enemy.
+fn spawn_boss(
+    commands: &mut Commands,
+    asset_server: &AssetServer,
+    catalog: &EnemyCatalog,
+    index: usize,
+    position: Vec2,
+    elapsed: f32,
+    rng: &mut impl Rng,
+) -> bool {
+    let entity = spawn_enemy(
+        commands,
+        asset_server,
+        catalog,
+        index,
+        position,
+        elapsed,
+        rng,
+    );
+    let archetype = &catalog.archetypes[index];
+    let Some(spec) = &archetype.boss else {
+        return false;
+    };
 
-    let current = timer.timer.duration().as_secs_f32();
-    let new_duration = (current * ENEMY_SPAWN_ACCELERATION).max(0.6);
-    timer
-        .timer
-        .set_duration(Duration::from_secs_f32(new_duration));
+    commands.entity(entity).insert(Boss {
+        name: spec.name.clone(),
+        max_health: archetype.health.at(elapsed),
+        phase: 0,
+        cooldown: spec.phases.first().map_or(0.0, |phase| phase.cooldown),
+        next_pattern: 0,
+        action: BossAction::Stalk,
+        contact_cooldown: 0.0,
+    });
+    true
 }
 
 fn move_enemies(
     time: Res<Time>,
-    run_state: Res<RunState>,
+    arena: Res<Arena>,
+    catalog: Res<EnemyCatalog>,
     player_q: Query<&Transform, (With<Player>, Without<Enemy>)>,
-    mut enemies: Query<(
-        &Enemy,
-        &mut Transform,
-        &mut EnemyVelocity,
-        &mut Knockback,
-        &mut EnemyHealth,
-    )>,
+    mut enemies: Query<
+        (
+            &Enemy,
+            &mut Transform,
+            &mut EnemyVelocity,
+            &mut SteeringState,
+            &mut Knockback,
+        ),
+        Without<Boss>,
+    >,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
     let Ok(player_transform) = player_q.get_single() else {
         return;
     };
     let player_position = player_transform.translation.truncate();
-
-    for (enemy, mut transform, mut velocity, mut knockback, mut health) in &mut enemies {
-        if health.current <= 0.0 {
-            transform.translation.z = -999.0;
-            continue;
-        }
-
-        let direction = player_position - transform.translation.truncate();
-        if direction.length_squared() > 0.1 {
-            let desired = direction.normalize() * enemy.speed;
-            velocity.current = velocity.current.lerp(desired, ENEMY_TURN_SPEED);
+    let delta = time.delta_seconds();
+
+    for (enemy, mut transform, mut velocity, mut steering, mut knockback) in &mut enemies {
+        let offset = player_position - transform.translation.truncate();
+        let behavior = catalog
+            .archetypes
+            .get(enemy.archetype)
+            .map_or(SteeringBehavior::Chaser, |archetype| archetype.behavior);
+        match steer(behavior, enemy.speed, offset, &mut steering, delta) {
+            Steer::Toward(desired) => {
+                velocity.current = velocity
+                    .current
+                    .lerp(desired, smoothing(ENEMY_TURN_SPEED, delta));
+            }
+            Steer::Exactly(set) => velocity.current = set,
         }
 
         velocity.current += knockback.velocity;
-        knockback.velocity *= 0.92;
+        knockback.velocity *= decay(ENEMY_KNOCKBACK_DAMPING, delta);
 
-        transform.translation += velocity.current.extend(0.0) * time.delta_seconds();
+        // Knockback and dashes stop at the arena wall like everything else.
+        let next = arena.clamp(transform.translation.truncate() + velocity.current * delta);
+        transform.translation.x = next.x;
+        transform.translation.y = next.y;
     }
 }
 
-fn resolve_trail_hits(
-    mut commands: Commands,
-    mut score: ResMut<Score>,
-    mut currency: ResMut<Currency>,
-    assets: Res<GameAssets>,
-    mut enemies: Query<(Entity, &mut EnemyHealth, &Transform), With<Enemy>>,
-    trails: Query<(&TrailSegment, &Transform)>,
-) {
-    for (enemy_entity, mut health, enemy_transform) in &mut enemies {
-        let enemy_pos = enemy_transform.translation.truncate();
-        for (trail, trail_transform) in &trails {
-            if trail.remaining <= 0.0 {
-                continue;
-            }
-            let trail_pos = trail_transform.translation.truncate();
-            if enemy_pos.distance_squared(trail_pos) <= TRAIL_HIT_RADIUS.powi(2) {
-                health.current -= trail.damage;
-                if health.current <= 0.0 {
-                    commands.entity(enemy_entity).despawn_recursive();
-                    score.add(BASE_SCORE);
-                    currency.credit(1);
-                    if random::<f32>() < POWER_UP_DROP_CHANCE {
-                        spawn_powerup(
-                            &mut commands,
-                            &assets,
-                            &trail_transform.translation.truncate(),
-                        );
+enum Steer {
+    /// Ease toward this velocity at the usual turn rate.
+    Toward(Vec2),
+    /// Snap to this velocity, e.g. a dash.
+    Exactly(Vec2),
+}
+
+/// Desired velocity for one enemy, `offset` being the vector to the player.
+fn steer(
+    behavior: SteeringBehavior,
+    speed: f32,
+    offset: Vec2,
+    state: &mut SteeringState,
+    delta: f32,
+) -> Steer {
+    let distance = offset.length();
+    let toward = offset.normalize_or_zero();
+    let around = toward.perp() * state.circle_direction;
+
+    match behavior {
+        SteeringBehavior::Chaser => Steer::Toward(toward * speed),
+        SteeringBehavior::Charger {
+            trigger_range,
+            windup,
+            dash_speed,
+            dash_duration,
+            recover,
+        } => {
+            state.timer -= delta;
+            match state.charge {
+                ChargePhase::Approach => {
+                    if distance <= trigger_range {
+                        state.charge = ChargePhase::WindUp;
+                        state.timer = windup;
                     }
+                    Steer::Toward(toward * speed)
+                }
+                ChargePhase::WindUp => {
+                    if state.timer <= 0.0 {
+                        state.charge = ChargePhase::Dash;
+                        state.timer = dash_duration;
+                        state.heading = toward;
+                    }
+                    Steer::Toward(Vec2::ZERO)
+                }
+                ChargePhase::Dash => {
+                    if state.timer <= 0.0 {
+                        state.charge = ChargePhase::Recover;
+                        state.timer = recover;
+                    }
+                    Steer::Exactly(state.heading * dash_speed)
+                }
+                ChargePhase::Recover => {
+                    if state.timer <= 0.0 {
+                        state.charge = ChargePhase::Approach;
+                    }
+                    Steer::Toward(Vec2::ZERO)
                 }
-                break;
+            }
+        }
+        SteeringBehavior::Orbiter { radius } => {
+            // Pull onto the ring, then travel around it.
+            let radial = toward * ((distance - radius) / radius.max(1.0)).clamp(-1.0, 1.0);
+            Steer::Toward((radial + around).normalize_or_zero() * speed)
+        }
+        SteeringBehavior::Shooter { range } => {
+            if distance > range * 1.15 {
+                Steer::Toward(toward * speed)
+            } else if distance < range * 0.85 {
+                Steer::Toward(-toward * speed)
+            } else {
+                Steer::Toward(around * speed * 0.4)
             }
         }
     }
 }
 
-fn spawn_powerup(commands: &mut Commands, assets: &Res<GameAssets>, position: &Vec2) {
-    let mut rng = rand::thread_rng();
-    let roll = rng.gen::<f32>();
+/// Runs each boss's current phase: stalking between attacks, then working
+/// through the phase's patterns in order. Dashes are telegraphed by a flash
+/// and keep the heading locked at wind-up, so the player can sidestep them.
+#[allow(clippy::too_many_arguments)]
+fn update_bosses(
+    mut commands: Commands,
+    time: Res<Time>,
+    asset_server: Res<AssetServer>,
+    assets: Res<GameAssets>,
+    catalog: Res<EnemyCatalog>,
+    arena: Res<Arena>,
+    run_stats: Res<RunStats>,
+    player_q: Query<&Transform, (With<Player>, Without<Boss>)>,
+    mut bosses: Query<(
+        &Enemy,
+        &mut Boss,
+        &EnemyHealth,
+        &mut Transform,
+        &mut EnemyVelocity,
+        &mut Knockback,
+        &mut Sprite,
+    )>,
+    mut rng: ResMut<RunRng>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+    let delta = time.delta_seconds();
+
+    for (enemy, mut boss, health, mut transform, mut velocity, mut knockback, mut sprite) in
+        &mut bosses
+    {
+        let Some(archetype) = catalog.archetypes.get(enemy.archetype) else {
+            continue;
+        };
+        let Some(spec) = &archetype.boss else {
+            continue;
+        };
+
+        let phase_index = spec.phase_at(health.current / boss.max_health.max(1.0));
+        if phase_index != boss.phase {
+            boss.phase = phase_index;
+            boss.next_pattern = 0;
+            boss.cooldown = boss.cooldown.min(0.5);
+        }
+        let Some(phase) = spec.phases.get(boss.phase) else {
+            continue;
+        };
+
+        let position = transform.translation.truncate();
+        let toward = (player_pos - position).normalize_or_zero();
+        // Bosses don't get shoved around by hits.
+        knockback.velocity = Vec2::ZERO;
+        boss.contact_cooldown = (boss.contact_cooldown - delta).max(0.0);
+        let [r, g, b] = archetype.tint;
+        let mut color = Color::srgb(r, g, b);
+
+        boss.action = match boss.action {
+            BossAction::Stalk => {
+                velocity.current = velocity
+                    .current
+                    .lerp(toward * phase.speed, smoothing(ENEMY_TURN_SPEED, delta));
+                boss.cooldown -= delta;
+                if boss.cooldown > 0.0 || phase.patterns.is_empty() {
+                    BossAction::Stalk
+                } else {
+                    let pattern = &phase.patterns[boss.next_pattern % phase.patterns.len()];
+                    boss.next_pattern += 1;
+                    match pattern {
+                        BossPattern::Dash {
+                            windup,
+                            speed,
+                            duration,
+                        } => BossAction::WindUp {
+                            remaining: *windup,
+                            dash_velocity: toward * *speed,
+                            dash_duration: *duration,
+                        },
+                        BossPattern::Summon { archetype, count } => {
+                            if let Some(index) = catalog.index_of(archetype) {
+                                for i in 0..*count {
+                                    let angle = TAU * i as f32 / *count as f32;
+                                    spawn_enemy(
+                                        &mut commands,
+                                        &asset_server,
+                                        &catalog,
+                                        index,
+                                        arena.clamp(position + Vec2::from_angle(angle) * 90.0),
+                                        run_stats.time_survived,
+                                        &mut rng.spawning,
+                                    );
+                                }
+                            }
+                            boss.cooldown = phase.cooldown;
+                            BossAction::Stalk
+                        }
+                        BossPattern::Volley {
+                            shot,
+                            count,
+                            spread,
+                        } => {
+                            spawn_volley(
+                                &mut commands,
+                                &assets,
+                                position,
+                                toward,
+                                shot,
+                                *count,
+                                *spread,
+                            );
+                            boss.cooldown = phase.cooldown;
+                            BossAction::Stalk
+                        }
+                        BossPattern::Hazards {
+                            hazard,
+                            count,
+                            scatter,
+                        } => {
+                            for _ in 0..*count {
+                                let offset = Vec2::from_angle(rng.spawning.gen_range(0.0..TAU))
+                                    * rng.spawning.gen_range(0.0..=*scatter);
+                                spawn_hazard(
+                                    &mut commands,
+                                    &assets,
+                                    arena.clamp(player_pos + offset),
+                                    hazard,
+                                );
+                            }
+                            boss.cooldown = phase.cooldown;
+                            BossAction::Stalk
+                        }
+                    }
+                }
+            }
+            BossAction::WindUp {
+                remaining,
+                dash_velocity,
+                dash_duration,
+            } => {
+                velocity.current = velocity.current.lerp(Vec2::ZERO, smoothing(0.2, delta));
+                if (remaining * 24.0).sin() > 0.0 {
+                    color = Color::WHITE;
+                }
+                let remaining = remaining - delta;
+                if remaining > 0.0 {
+                    BossAction::WindUp {
+                        remaining,
+                        dash_velocity,
+                        dash_duration,
+                    }
+                } else {
+                    BossAction::Dash {
+                        remaining: dash_duration,
+                        velocity: dash_velocity,
+                    }
+                }
+            }
+            BossAction::Dash {
+                remaining,
+                velocity: dash,
+            } => {
+                velocity.current = dash;
+                let remaining = remaining - delta;
+                if remaining > 0.0 {
+                    BossAction::Dash {
+                        remaining,
+                        velocity: dash,
+                    }
+                } else {
+                    boss.cooldown = phase.cooldown;
+                    BossAction::Stalk
+                }
+            }
+        };
+
+        sprite.color = color;
+        let next = arena.clamp(position + velocity.current * delta);
+        transform.translation.x = next.x;
+        transform.translation.y = next.y;
+    }
+}
+
+/// Counts down each ranged enemy's attack and uses it when ready. Volleys
+/// hold until the player is in range; trails are dropped regardless.
+fn fire_enemy_attacks(
+    mut commands: Commands,
+    time: Res<Time>,
+    assets: Res<GameAssets>,
+    catalog: Res<EnemyCatalog>,
+    player_q: Query<&Transform, (With<Player>, Without<Enemy>)>,
+    mut enemies: Query<(&Enemy, &Transform, &EnemyHealth, &mut AttackCooldown), Without<Boss>>,
+) {
+    let Ok(player_transform) = player_q.get_single() else {
+        return;
+    };
+    let player_pos = player_transform.translation.truncate();
+
+    for (enemy, transform, health, mut cooldown) in &mut enemies {
+        if health.current <= 0.0 {
+            continue;
+        }
+        let Some(attack) = catalog
+            .archetypes
+            .get(enemy.archetype)
+            .and_then(|archetype| archetype.attack)
+        else {
+            continue;
+        };
+
+        cooldown.remaining -= time.delta_seconds();
+        if cooldown.remaining > 0.0 {
+            continue;
+        }
+
+        let position = transform.translation.truncate();
+        match attack {
+            EnemyAttack::Volley {
+                cooldown: interval,
+                range,
+                shot,
+                count,
+                spread,
+            } => {
+                let offset = player_pos - position;
+                if offset.length_squared() > range * range {
+                    continue;
+                }
+                spawn_volley(
+                    &mut commands,
+                    &assets,
+                    position,
+                    offset.normalize_or_zero(),
+                    &shot,
+                    count,
+                    spread,
+                );
+                cooldown.remaining = interval;
+            }
+            EnemyAttack::Trail {
+                cooldown: interval,
+                hazard,
+            } => {
+                spawn_hazard(&mut commands, &assets, position, &hazard);
+                cooldown.remaining = interval;
+            }
+        }
+    }
+}
+
+/// Fans `count` shots across `spread` radians around `aim`.
+fn spawn_volley(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    origin: Vec2,
+    aim: Vec2,
+    shot: &ProjectileSpec,
+    count: u32,
+    spread: f32,
+) {
+    if aim == Vec2::ZERO {
+        return;
+    }
+    let count = count.max(1);
+    for index in 0..count {
+        let angle = if count > 1 {
+            spread * (index as f32 / (count - 1) as f32 - 0.5)
+        } else {
+            0.0
+        };
+        let direction = Vec2::from_angle(angle).rotate(aim);
+
+        commands.spawn((
+            SpriteBundle {
+                texture: assets.trail_segment.clone(),
+                transform: Transform::from_xyz(origin.x, origin.y, 0.45),
+                sprite: Sprite {
+                    color: Color::srgb(1.0, 0.35, 0.3),
+                    custom_size: Some(Vec2::splat(shot.radius * 2.5)),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            EnemyProjectile {
+                velocity: direction * shot.speed,
+                age: 0.0,
+                lifetime: shot.lifetime,
+                damage: shot.damage,
+            },
+            Hitbox::Circle {
+                radius: shot.radius,
+            },
+            Interpolated::default(),
+            StateScoped(AppState::InGame),
+        ));
+    }
+}
+
+fn spawn_hazard(commands: &mut Commands, assets: &GameAssets, position: Vec2, spec: &HazardSpec) {
+    commands.spawn((
+        SpriteBundle {
+            texture: assets.trail_segment.clone(),
+            transform: Transform::from_xyz(position.x, position.y, 0.05),
+            sprite: Sprite {
+                color: hazard_color(&spec.effect).with_alpha(0.0),
+                custom_size: Some(Vec2::splat(spec.radius * 2.0)),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        Hazard {
+            effect: spec.effect,
+            arming: HAZARD_ARM_TIME,
+            remaining: spec.duration,
+            tick: 0.0,
+        },
+        Hitbox::Circle {
+            radius: spec.radius,
+        },
+        ParticleEmitter::new(
+            ParticleSpec {
+                color: hazard_color(&spec.effect),
+                ..HAZARD_PARTICLES
+            },
+            HAZARD_PARTICLE_RATE,
+        ),
+        StateScoped(AppState::InGame),
+    ));
+}
+
+fn hazard_color(effect: &HazardEffect) -> Color {
+    match effect {
+        HazardEffect::Slow { .. } => Color::srgb(0.45, 0.4, 1.0),
+        HazardEffect::Damage { .. } => Color::srgb(1.0, 0.4, 0.15),
+    }
+}
+
+fn update_enemy_projectiles(
+    mut commands: Commands,
+    time: Res<Time>,
+    arena: Res<Arena>,
+    mut projectiles: Query<(Entity, &mut Transform, &mut EnemyProjectile)>,
+) {
+    for (entity, mut transform, mut projectile) in &mut projectiles {
+        projectile.age += time.delta_seconds();
+        let next = transform.translation.truncate() + projectile.velocity * time.delta_seconds();
+        if projectile.age >= projectile.lifetime || !arena.contains(next) {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+
+        transform.translation.x = next.x;
+        transform.translation.y = next.y;
+    }
+}
+
+/// Ages hazards, fading them in while they arm and out as they expire.
+fn tick_hazards(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut hazards: Query<(Entity, &mut Hazard, &mut Sprite)>,
+) {
+    let delta = time.delta_seconds();
+    for (entity, mut hazard, mut sprite) in &mut hazards {
+        if !hazard.is_armed() {
+            hazard.arming -= delta;
+            let t = 1.0 - hazard.arming.max(0.0) / HAZARD_ARM_TIME;
+            sprite.color.set_alpha(0.25 * t);
+            continue;
+        }
+
+        hazard.remaining -= delta;
+        hazard.tick = (hazard.tick - delta).max(0.0);
+        if hazard.remaining <= 0.0 {
+            commands.entity(entity).despawn_recursive();
+            continue;
+        }
+        sprite.color.set_alpha(0.55 * hazard.remaining.min(1.0));
+    }
+}
+
+/// Enemy shots and damage zones touching the player.
+fn resolve_enemy_fire(
+    mut commands: Commands,
+    mut hits: PlayerHits,
+    mut player_query: Query<(&Transform, &Hitbox, &mut Knockback), With<Player>>,
+    projectile_grid: Res<SpatialGrid<EnemyProjectile>>,
+    hazard_grid: Res<SpatialGrid<Hazard>>,
+    projectiles: Query<&EnemyProjectile>,
+    mut hazards: Query<&mut Hazard>,
+) {
+    let Ok((player_transform, player_hitbox, mut knockback)) = player_query.get_single_mut() else {
+        return;
+    };
+    let body = player_hitbox.placed(player_transform);
+
+    for entry in projectile_grid.query(body) {
+        let Ok(projectile) = projectiles.get(entry.entity) else {
+            continue;
+        };
+        commands.entity(entry.entity).despawn_recursive();
+        let push = projectile.velocity.normalize_or_zero() * ENEMY_PROJECTILE_KNOCKBACK;
+        hits.hit(projectile.damage, &mut knockback, push);
+    }
+
+    for entry in hazard_grid.query(body) {
+        let Ok(mut hazard) = hazards.get_mut(entry.entity) else {
+            continue;
+        };
+        let HazardEffect::Damage { damage, interval } = hazard.effect else {
+            continue;
+        };
+        if !hazard.is_armed() || hazard.tick > 0.0 {
+            continue;
+        }
+        hazard.tick = interval;
+        hits.hit(damage, &mut knockback, Vec2::ZERO);
+    }
+}
+
+/// Everything a kill pays out: score through the combo, currency, the
+/// power-up drop roll, splits, boss rewards, particles and run stats.
+/// Enemies that ran into the player still split, burst and count, but pay
+/// nothing: the player was hurt, not rewarded.
+#[derive(SystemParam)]
+struct KillRewards<'w> {
+    score: ResMut<'w, Score>,
+    currency: ResMut<'w, Currency>,
+    combo: ResMut<'w, ComboState>,
+    milestones: EventWriter<'w, ComboMilestoneEvent>,
+    stats: ResMut<'w, RunStats>,
+    assets: Res<'w, GameAssets>,
+    asset_server: Res<'w, AssetServer>,
+    catalog: Res<'w, EnemyCatalog>,
+    director: ResMut<'w, WaveDirector>,
+    particles: Particles<'w>,
+    rng: ResMut<'w, RunRng>,
+}
+
+impl KillRewards<'_> {
+    fn grant(&mut self, commands: &mut Commands, kill: &EnemyKilled) {
+        let &EnemyKilled {
+            enemy,
+            position,
+            archetype,
+            source,
+        } = kill;
+        commands.entity(enemy).despawn_recursive();
+        self.stats.record_kill(source);
+        self.split(commands, archetype, position);
+        self.boss_reward(commands, archetype, position);
+        self.burst(commands, archetype, position);
+        if source == KillSource::Contact {
+            return;
+        }
+
+        let chain = self.combo.register_kill();
+        self.stats.peak_combo = self.stats.peak_combo.max(chain);
+        let points = self.combo.apply(BASE_SCORE);
+        self.score.add(points);
+        if self.combo.at_milestone() {
+            self.milestones.send(ComboMilestoneEvent { count: chain });
+        }
+
+        self.currency.credit(1);
+        self.stats.currency_earned += 1;
+        if self.rng.drops.gen::<f32>() < POWER_UP_DROP_CHANCE {
+            let kind = roll_powerup(&mut self.rng.drops);
+            spawn_powerup(commands, &self.assets, position, kind);
+        }
+    }
+
+    /// Pays out a boss's guaranteed drop and lets the director move on.
+    fn boss_reward(&mut self, commands: &mut Commands, archetype: usize, position: Vec2) {
+        let Some(boss) = self
+            .catalog
+            .archetypes
+            .get(archetype)
+            .and_then(|archetype| archetype.boss.as_ref())
+        else {
+            return;
+        };
+
+        self.director.boss_alive = false;
+        self.currency.credit(boss.reward.currency);
+        self.stats.currency_earned += boss.reward.currency;
+        let count = boss.reward.powerups.len();
+        for (index, kind) in boss.reward.powerups.iter().enumerate() {
+            let offset = Vec2::from_angle(TAU * index as f32 / count as f32) * 36.0;
+            spawn_powerup(commands, &self.assets, position + offset, *kind);
+        }
+    }
+
+    /// Scatters a puff of particles in the archetype's tint.
+    fn burst(&mut self, commands: &mut Commands, archetype: usize, position: Vec2) {
+        let [r, g, b] = self
+            .catalog
+            .archetypes
+            .get(archetype)
+            .map_or([1.0; 3], |archetype| archetype.tint);
+        let spec = ParticleSpec {
+            color: Color::srgb(r, g, b),
+            ..DEATH_PARTICLES
+        };
+        self.particles.burst(
+            commands,
+            &mut self.rng.cosmetic,
+            position,
+            DEATH_PARTICLE_COUNT,
+            &spec,
+        );
+    }
+
+    /// Releases the archetype's split children in a ring around `position`.
+    fn split(&mut self, commands: &mut Commands, archetype: usize, position: Vec2) {
+        let Some(split) = self
+            .catalog
+            .archetypes
+            .get(archetype)
+            .and_then(|archetype| archetype.split.as_ref())
+        else {
+            return;
+        };
+        let Some(child) = self.catalog.index_of(&split.archetype) else {
+            return;
+        };
+
+        let start = self.rng.spawning.gen_range(0.0..TAU);
+        for i in 0..split.count {
+            let direction = Vec2::from_angle(start + TAU * i as f32 / split.count as f32);
+            let entity = spawn_enemy(
+                commands,
+                &self.asset_server,
+                &self.catalog,
+                child,
+                position + direction * 18.0,
+                self.stats.time_survived,
+                &mut self.rng.spawning,
+            );
+            commands.entity(entity).insert(Knockback {
+                velocity: direction * ENEMY_KNOCKBACK,
+            });
+        }
+    }
+}
+
+/// Applies every `DamageEvent` sent this frame. An enemy dies once, however
+/// many hits land on the frame it drops to zero.
+fn apply_damage(
+    mut commands: Commands,
+    mut particles: Particles,
+    mut rng: ResMut<RunRng>,
+    mut damage: EventReader<DamageEvent>,
+    mut kills: EventWriter<EnemyKilled>,
+    mut enemies: Query<(&Enemy, &mut EnemyHealth, &mut Knockback, &Transform)>,
+) {
+    for event in damage.read() {
+        let Ok((enemy, mut health, mut knockback, transform)) = enemies.get_mut(event.target)
+        else {
+            continue;
+        };
+        if health.current <= 0.0 {
+            continue;
+        }
+
+        health.current -= event.amount;
+        knockback.velocity += event.knockback;
+        let position = transform.translation.truncate();
+        particles.burst(
+            &mut commands,
+            &mut rng.cosmetic,
+            position,
+            HIT_PARTICLE_COUNT,
+            &HIT_PARTICLES,
+        );
+        if health.current <= 0.0 {
+            kills.send(EnemyKilled {
+                enemy: event.target,
+                position,
+                archetype: enemy.archetype,
+                source: event.source,
+            });
+        }
+    }
+}
+
+fn handle_enemy_kills(
+    mut commands: Commands,
+    mut kills: EventReader<EnemyKilled>,
+    mut rewards: KillRewards,
+) {
+    for kill in kills.read() {
+        rewards.grant(&mut commands, kill);
+    }
+}
+
+/// Tests enemies against the thread as a polyline, running right up to the
+/// player. An enemy touching it takes the newer end's damage, then can't be
+/// hurt by the trail again for `TRAIL_DAMAGE_INTERVAL`.
+fn resolve_trail_hits(
+    time: Res<Time>,
+    grid: Res<SpatialGrid<Enemy>>,
+    mut damage: EventWriter<DamageEvent>,
+    player: Query<(&Transform, &Player, &TrailHistory)>,
+    mut enemies: Query<(&EnemyHealth, &mut TrailHitCooldown), With<Enemy>>,
+) {
+    for (_, mut cooldown) in &mut enemies {
+        cooldown.remaining = (cooldown.remaining - time.delta_seconds()).max(0.0);
+    }
+
+    let Ok((transform, player, history)) = player.get_single() else {
+        return;
+    };
+    let head = history
+        .points
+        .back()
+        .filter(|_| player.weapon == WeaponType::Trail)
+        .map(|newest| {
+            (
+                newest.position,
+                transform.translation.truncate(),
+                newest.damage,
+            )
+        });
+    let segments = history
+        .points
+        .iter()
+        .zip(history.points.iter().skip(1))
+        .map(|(from, to)| (from.position, to.position, to.damage))
+        .chain(head);
+
+    for (from, to, amount) in segments {
+        for entry in grid.query(Shape::capsule(from, to, TRAIL_WIDTH * 0.5)) {
+            let Ok((health, mut cooldown)) = enemies.get_mut(entry.entity) else {
+                continue;
+            };
+            if health.current <= 0.0 || cooldown.remaining > 0.0 {
+                continue;
+            }
+
+            cooldown.remaining = TRAIL_DAMAGE_INTERVAL;
+            damage.send(DamageEvent {
+                target: entry.entity,
+                amount,
+                source: KillSource::Trail,
+                knockback: Vec2::ZERO,
+            });
+        }
+    }
+}
+
+/// Rebuilds the enemy grid from where enemies ended up this frame.
+fn index_enemies(
+    mut grid: ResMut<SpatialGrid<Enemy>>,
+    enemies: Query<(Entity, &Transform, &Hitbox), With<Enemy>>,
+) {
+    grid.clear();
+    for (entity, transform, hitbox) in &enemies {
+        grid.insert(entity, hitbox.placed(transform));
+    }
+}
+
+fn index_powerups(
+    mut grid: ResMut<SpatialGrid<PowerUp>>,
+    powerups: Query<(Entity, &Transform, &Hitbox), With<PowerUp>>,
+) {
+    grid.clear();
+    for (entity, transform, hitbox) in &powerups {
+        grid.insert(entity, hitbox.placed(transform));
+    }
+}
+
+/// Rebuilds the enemy shot and hazard grids once shots have moved and spent
+/// hazards are gone. Hazards don't move, so the hazard grid also serves the
+/// next step's slow check in `move_player`.
+fn index_enemy_fire(
+    mut projectile_grid: ResMut<SpatialGrid<EnemyProjectile>>,
+    mut hazard_grid: ResMut<SpatialGrid<Hazard>>,
+    projectiles: Query<(Entity, &Transform, &Hitbox), With<EnemyProjectile>>,
+    hazards: Query<(Entity, &Transform, &Hitbox), With<Hazard>>,
+) {
+    projectile_grid.clear();
+    for (entity, transform, hitbox) in &projectiles {
+        projectile_grid.insert(entity, hitbox.placed(transform));
+    }
+    hazard_grid.clear();
+    for (entity, transform, hitbox) in &hazards {
+        hazard_grid.insert(entity, hitbox.placed(transform));
+    }
+}
+
+fn tick_combo(time: Res<Time>, mut combo: ResMut<ComboState>) {
+    combo.tick(time.delta_seconds());
+}
+
+fn tick_run_stats(time: Res<Time>, mut stats: ResMut<RunStats>) {
+    stats.time_survived += time.delta_seconds();
+}
+
+fn roll_powerup(rng: &mut impl Rng) -> PowerUpKind {
+    let roll = rng.gen::<f32>();
     let cumulative = [
         (PowerUpKind::Currency, POWER_UP_CURRENCY_WEIGHT),
         (PowerUpKind::Health, POWER_UP_HEART_WEIGHT),
@@ -494,15 +1815,21 @@ fn spawn_powerup(commands: &mut Commands, assets: &Res<GameAssets>, position: &V
     ];
 
     let mut total = 0.0;
-    let mut selected = PowerUpKind::Currency;
     for (kind, weight) in cumulative {
         total += weight;
         if roll <= total {
-            selected = kind;
-            break;
+            return kind;
         }
     }
+    PowerUpKind::Currency
+}
 
+fn spawn_powerup(
+    commands: &mut Commands,
+    assets: &GameAssets,
+    position: Vec2,
+    selected: PowerUpKind,
+) {
     let texture = match selected {
         PowerUpKind::Currency => assets.powerup_currency.clone(),
         PowerUpKind::Health => assets.powerup_health.clone(),
@@ -516,55 +1843,102 @@ fn spawn_powerup(commands: &mut Commands, assets: &Res<GameAssets>, position: &V
             texture,
             transform: Transform::from_xyz(position.x, position.y, 0.4),
             sprite: Sprite {
-                custom_size: Some(Vec2::splat(24.0)),
+                custom_size: Some(Vec2::splat(POWER_UP_SIZE)),
                 ..Default::default()
             },
             ..Default::default()
         },
         PowerUp { kind: selected },
+        Hitbox::Circle {
+            radius: POWER_UP_SIZE * 0.5,
+        },
         PowerUpLifetime {
             timer: Timer::from_seconds(POWER_UP_LIFETIME, TimerMode::Once),
         },
+        StateScoped(AppState::InGame),
     ));
 }
 
-fn resolve_player_collisions(
-    mut commands: Commands,
-    mut run_state: ResMut<RunState>,
-    mut player_health: ResMut<PlayerHealth>,
-    mut shield: ResMut<ShieldState>,
-    mut score: ResMut<Score>,
-    mut player_query: Query<(&Transform, &mut Knockback), With<Player>>,
-    enemies: Query<(Entity, &Transform), With<Enemy>>,
-    mut hit_events: EventWriter<PlayerHitEvent>,
-) {
-    if !run_state.is_running() {
-        return;
+fn powerup_color(kind: PowerUpKind) -> Color {
+    match kind {
+        PowerUpKind::Currency => Color::srgb(1.0, 0.85, 0.3),
+        PowerUpKind::Health => Color::srgb(1.0, 0.4, 0.45),
+        PowerUpKind::Shield => Color::srgb(0.45, 0.8, 1.0),
+        PowerUpKind::Accuracy => Color::srgb(0.5, 1.0, 0.6),
+        PowerUpKind::WaveBlast => Color::srgb(0.75, 0.55, 1.0),
+    }
+}
+
+/// Damage to the player from any source. An active shield absorbs it;
+/// otherwise it costs health and score, breaks the combo through
+/// `PlayerHitEvent` and ends the run at zero health.
+#[derive(SystemParam)]
+struct PlayerHits<'w> {
+    next_state: ResMut<'w, NextState<AppState>>,
+    health: ResMut<'w, PlayerHealth>,
+    shield: Res<'w, ShieldState>,
+    score: ResMut<'w, Score>,
+    run_stats: ResMut<'w, RunStats>,
+    events: EventWriter<'w, PlayerHitEvent>,
+}
+
+impl PlayerHits<'_> {
+    /// Hurts the player unless the shield is up.
+    fn hit(&mut self, damage: u32, knockback: &mut Knockback, push: Vec2) {
+        if self.shield.is_active() {
+            return;
+        }
+
+        self.health.damage(damage);
+        self.run_stats.damage_taken += damage;
+        if push != Vec2::ZERO {
+            knockback.velocity = push;
+        }
+        self.score.current = self.score.current.saturating_sub(BASE_SCORE / 2);
+        self.events.send(PlayerHitEvent);
+
+        if self.health.is_dead() {
+            self.next_state.set(AppState::GameOver);
+        }
     }
+}
 
-    let Ok((player_transform, mut knockback)) = player_query.get_single_mut() else {
+fn resolve_player_collisions(
+    grid: Res<SpatialGrid<Enemy>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut hits: PlayerHits,
+    mut player_query: Query<(&Transform, &Hitbox, &mut Knockback), With<Player>>,
+    mut bosses: Query<&mut Boss>,
+) {
+    let Ok((player_transform, hitbox, mut knockback)) = player_query.get_single_mut() else {
         return;
     };
     let player_pos = player_transform.translation.truncate();
 
-    for (entity, transform) in &enemies {
-        let enemy_pos = transform.translation.truncate();
-        if enemy_pos.distance_squared(player_pos) <= (PLAYER_RADIUS.powi(2)) as f32 {
-            commands.entity(entity).despawn_recursive();
-            if shield.is_active() {
-                continue;
+    for entry in grid.query(hitbox.placed(player_transform)) {
+        let damage = match bosses.get_mut(entry.entity) {
+            // Bosses survive contact and hurt on a cooldown instead.
+            Ok(mut boss) => {
+                if boss.contact_cooldown > 0.0 {
+                    continue;
+                }
+                boss.contact_cooldown = BOSS_CONTACT_COOLDOWN;
+                BOSS_CONTACT_DAMAGE
             }
-
-            player_health.damage(PLAYER_COLLISION_DAMAGE);
-            knockback.velocity =
-                (player_pos - enemy_pos).normalize_or_zero() * PLAYER_KNOCKBACK_STRENGTH;
-            score.current = score.current.saturating_sub(BASE_SCORE / 2);
-            hit_events.send(PlayerHitEvent);
-
-            if player_health.is_dead() {
-                run_state.end();
+            // Anything else dies on impact, through the usual death pipeline.
+            Err(_) => {
+                damage.send(DamageEvent {
+                    target: entry.entity,
+                    amount: f32::INFINITY,
+                    source: KillSource::Contact,
+                    knockback: Vec2::ZERO,
+                });
+                PLAYER_COLLISION_DAMAGE
             }
-        }
+        };
+        let push =
+            (player_pos - entry.shape.center()).normalize_or_zero() * PLAYER_KNOCKBACK_STRENGTH;
+        hits.hit(damage, &mut knockback, push);
     }
 }
 
@@ -580,59 +1954,94 @@ fn tick_powerups(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn apply_powerup_pickups(
     mut commands: Commands,
+    mut particles: Particles,
+    mut rng: ResMut<RunRng>,
     mut currency: ResMut<Currency>,
     mut player_health: ResMut<PlayerHealth>,
     mut shield: ResMut<ShieldState>,
     mut stats: ResMut<PlayerStats>,
     mut upgrades: ResMut<PurchasedUpgrades>,
-    player_q: Query<&Transform, With<Player>>,
-    mut powerups: Query<(Entity, &PowerUp, &Transform)>,
+    mut run_stats: ResMut<RunStats>,
+    grid: Res<SpatialGrid<PowerUp>>,
+    player_q: Query<(&Transform, &Hitbox), With<Player>>,
+    powerups: Query<&PowerUp>,
 ) {
-    let Ok(player_transform) = player_q.get_single() else {
+    let Ok((player_transform, hitbox)) = player_q.get_single() else {
         return;
     };
-    let player_pos = player_transform.translation.truncate();
 
-    for (entity, powerup, transform) in &mut powerups {
-        let diff = transform.translation.truncate() - player_pos;
-        if diff.length_squared() <= (PLAYER_RADIUS * PLAYER_RADIUS) as f32 {
-            commands.entity(entity).despawn_recursive();
-            match powerup.kind {
-                PowerUpKind::Currency => currency.credit(5),
-                PowerUpKind::Health => player_health.heal(1),
-                PowerUpKind::Shield => {
-                    shield.remaining = SHIELD_DURATION + upgrades.shield_duration_bonus();
-                    shield.duration = SHIELD_DURATION + upgrades.shield_duration_bonus();
-                }
-                PowerUpKind::Accuracy => stats.acceleration += 0.02,
-                PowerUpKind::WaveBlast => upgrades.trail_damage_level += 1,
+    for entry in grid.query(hitbox.placed(player_transform)) {
+        let Ok(powerup) = powerups.get(entry.entity) else {
+            continue;
+        };
+
+        commands.entity(entry.entity).despawn_recursive();
+        run_stats.pickups += 1;
+        let spec = ParticleSpec {
+            color: powerup_color(powerup.kind),
+            ..PICKUP_PARTICLES
+        };
+        particles.burst(
+            &mut commands,
+            &mut rng.cosmetic,
+            entry.shape.center(),
+            PICKUP_PARTICLE_COUNT,
+            &spec,
+        );
+        match powerup.kind {
+            PowerUpKind::Currency => {
+                currency.credit(5);
+                run_stats.currency_earned += 5;
             }
+            PowerUpKind::Health => player_health.heal(1),
+            PowerUpKind::Shield => {
+                shield.remaining = SHIELD_DURATION + upgrades.shield_duration_bonus();
+                shield.duration = SHIELD_DURATION + upgrades.shield_duration_bonus();
+            }
+            PowerUpKind::Accuracy => stats.acceleration += 0.02,
+            PowerUpKind::WaveBlast => upgrades.trail_damage_level += 1,
         }
     }
 }
 
-fn update_shield_state(time: Res<Time>, mut shield: ResMut<ShieldState>) {
+fn update_shield_state(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut shield: ResMut<ShieldState>,
+    mut particles: Particles,
+    mut rng: ResMut<RunRng>,
+    player: Query<&Transform, With<Player>>,
+) {
     if shield.remaining > 0.0 {
         shield.remaining = (shield.remaining - time.delta_seconds()).max(0.0);
+        if shield.remaining == 0.0 {
+            if let Ok(transform) = player.get_single() {
+                particles.burst(
+                    &mut commands,
+                    &mut rng.cosmetic,
+                    transform.translation.truncate(),
+                    SHIELD_BREAK_PARTICLE_COUNT,
+                    &SHIELD_BREAK_PARTICLES,
+                );
+            }
+        }
     }
 }
 
 fn advance_wave_projectile_timer(
-    time: Res<Time>,
-    run_state: Res<RunState>,
     mut commands: Commands,
     assets: Res<GameAssets>,
-    mut player_query: Query<(&Transform, &Player, &PlayerVelocity)>,
+    mut player_query: Query<(&Transform, &mut Player, &PlayerVelocity)>,
 ) {
-    if !run_state.is_running() {
-        return;
-    }
-
-    let Ok((transform, player, velocity)) = player_query.get_single_mut() else {
+    let Ok((transform, mut player, velocity)) = player_query.get_single_mut() else {
         return;
     };
+    if player.weapon != WeaponType::Wave {
+        return;
+    }
     if player.wave_cooldown > 0.0 || velocity.current.length_squared() < 1200.0 {
         return;
     }
@@ -642,25 +2051,46 @@ fn advance_wave_projectile_timer(
         return;
     }
 
-    let spawn_position = transform.translation.truncate() + forward * 24.0;
-
-    commands.spawn((
-        SpriteBundle {
-            texture: assets.wave_projectile.clone(),
-            transform: Transform::from_xyz(spawn_position.x, spawn_position.y, 0.35),
-            sprite: Sprite {
-                custom_size: Some(Vec2::new(32.0, 16.0)),
+    let origin = transform.translation.truncate() + forward * 24.0;
+    let count = WAVE_PROJECTILE_COUNT.max(1);
+    for index in 0..count {
+        // Fan the volley symmetrically around the direction of travel.
+        let angle = if count > 1 {
+            WAVE_SPREAD_ANGLE * (index as f32 / (count - 1) as f32 - 0.5)
+        } else {
+            0.0
+        };
+        let direction = Vec2::from_angle(angle).rotate(forward);
+
+        commands.spawn((
+            SpriteBundle {
+                texture: assets.wave_projectile.clone(),
+                transform: Transform::from_xyz(origin.x, origin.y, 0.35)
+                    .with_rotation(Quat::from_rotation_z(direction.to_angle())),
+                sprite: Sprite {
+                    custom_size: Some(WAVE_PROJECTILE_SIZE),
+                    ..Default::default()
+                },
                 ..Default::default()
             },
-            ..Default::default()
-        },
-        WaveProjectile {
-            velocity: forward * WAVE_SPEED,
-            age: 0.0,
-            lifetime: WAVE_LIFETIME,
-            damage: WAVE_DAMAGE,
-        },
-    ));
+            Hitbox::Capsule {
+                half_length: (WAVE_PROJECTILE_SIZE.x - WAVE_PROJECTILE_SIZE.y) * 0.5,
+                radius: WAVE_PROJECTILE_SIZE.y * 0.5,
+            },
+            Interpolated::default(),
+            WaveProjectile {
+                velocity: direction * WAVE_SPEED,
+                age: 0.0,
+                lifetime: WAVE_LIFETIME,
+                damage: WAVE_DAMAGE,
+                pierce: WAVE_PIERCE,
+                hits: Vec::new(),
+            },
+            StateScoped(AppState::InGame),
+        ));
+    }
+
+    player.wave_cooldown = WAVE_COOLDOWN;
 }
 
 fn update_wave_projectiles(
@@ -676,17 +2106,193 @@ fn update_wave_projectiles(
         }
 
         transform.translation += projectile.velocity.extend(0.0) * time.delta_seconds();
+    }
+}
+
+fn resolve_wave_hits(
+    mut commands: Commands,
+    grid: Res<SpatialGrid<Enemy>>,
+    mut damage: EventWriter<DamageEvent>,
+    mut projectiles: Query<(Entity, &Transform, &Hitbox, &mut WaveProjectile)>,
+    enemies: Query<&EnemyHealth, With<Enemy>>,
+) {
+    for (projectile_entity, projectile_transform, hitbox, mut projectile) in &mut projectiles {
+        let direction = projectile.velocity.normalize_or_zero();
+
+        for entry in grid.query(hitbox.placed(projectile_transform)) {
+            let enemy_entity = entry.entity;
+            if projectile.hits.contains(&enemy_entity) {
+                continue;
+            }
+            let Ok(health) = enemies.get(enemy_entity) else {
+                continue;
+            };
+            if health.current <= 0.0 {
+                continue;
+            }
+
+            damage.send(DamageEvent {
+                target: enemy_entity,
+                amount: projectile.damage as f32,
+                source: KillSource::Wave,
+                knockback: direction * ENEMY_KNOCKBACK,
+            });
+            projectile.hits.push(enemy_entity);
+
+            if projectile.pierce == 0 {
+                commands.entity(projectile_entity).despawn_recursive();
+                break;
+            }
+            projectile.pierce -= 1;
+        }
+    }
+}
+
+/// The look and motion particles are drawn from; every range is sampled
+/// per particle.
+#[derive(Clone, Debug)]
+pub struct ParticleSpec {
+    pub color: Color,
+    pub size: Range<f32>,
+    pub speed: Range<f32>,
+    pub lifetime: Range<f32>,
+}
+
+const HIT_PARTICLES: ParticleSpec = ParticleSpec {
+    color: Color::srgb(1.0, 0.95, 0.8),
+    size: 4.0..8.0,
+    speed: 60.0..160.0,
+    lifetime: 0.15..0.3,
+};
+
+const DEATH_PARTICLES: ParticleSpec = ParticleSpec {
+    color: Color::WHITE,
+    size: 8.0..14.0,
+    speed: 80.0..220.0,
+    lifetime: 0.3..0.6,
+};
+
+const PICKUP_PARTICLES: ParticleSpec = ParticleSpec {
+    color: Color::WHITE,
+    size: 6.0..10.0,
+    speed: 40.0..120.0,
+    lifetime: 0.3..0.5,
+};
+
+const SHIELD_BREAK_PARTICLES: ParticleSpec = ParticleSpec {
+    color: Color::srgb(0.45, 0.8, 1.0),
+    size: 6.0..12.0,
+    speed: 140.0..260.0,
+    lifetime: 0.3..0.5,
+};
+
+const PLAYER_HIT_PARTICLES: ParticleSpec = ParticleSpec {
+    color: Color::srgb(1.0, 0.3, 0.35),
+    size: 6.0..12.0,
+    speed: 100.0..240.0,
+    lifetime: 0.25..0.45,
+};
+
+const HAZARD_PARTICLES: ParticleSpec = ParticleSpec {
+    color: Color::srgb(1.0, 0.5, 0.2),
+    size: 6.0..10.0,
+    speed: 10.0..40.0,
+    lifetime: 0.4..0.8,
+};
+
+/// Emits particles from its entity's position at `rate` per second.
+#[derive(Component, Clone, Debug)]
+pub struct ParticleEmitter {
+    pub spec: ParticleSpec,
+    pub rate: f32,
+    /// Fractional particles carried over between frames.
+    pub pending: f32,
+}
+
+impl ParticleEmitter {
+    pub fn new(spec: ParticleSpec, rate: f32) -> Self {
+        Self {
+            spec,
+            rate,
+            pending: 0.0,
+        }
+    }
+}
 
-        // TODO: Add collision detection back
-        // For now, projectiles just expire naturally after their lifetime
+/// Spawns particles within the global budget.
+#[derive(SystemParam)]
+pub struct Particles<'w> {
+    budget: ResMut<'w, ParticleBudget>,
+    assets: Res<'w, GameAssets>,
+}
+
+impl Particles<'_> {
+    /// Sends up to `count` particles out from `position` in random directions,
+    /// rolled from `rng` (the run's cosmetic stream).
+    pub fn burst(
+        &mut self,
+        commands: &mut Commands,
+        rng: &mut impl Rng,
+        position: Vec2,
+        count: u32,
+        spec: &ParticleSpec,
+    ) {
+        for _ in 0..self.budget.reserve(count as usize) {
+            let velocity =
+                Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(spec.speed.clone());
+            commands.spawn((
+                SpriteBundle {
+                    texture: self.assets.trail_segment.clone(),
+                    transform: Transform::from_xyz(position.x, position.y, 0.5),
+                    sprite: Sprite {
+                        color: spec.color,
+                        custom_size: Some(Vec2::splat(rng.gen_range(spec.size.clone()))),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                },
+                Particle {
+                    velocity,
+                    lifetime: rng.gen_range(spec.lifetime.clone()),
+                    age: 0.0,
+                },
+                StateScoped(AppState::InGame),
+            ));
+        }
+    }
+}
+
+fn run_particle_emitters(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut particles: Particles,
+    mut rng: ResMut<RunRng>,
+    mut emitters: Query<(&GlobalTransform, &mut ParticleEmitter)>,
+) {
+    for (transform, mut emitter) in &mut emitters {
+        emitter.pending += emitter.rate * time.delta_seconds();
+        let count = emitter.pending.floor();
+        if count < 1.0 {
+            continue;
+        }
+        emitter.pending -= count;
+        particles.burst(
+            &mut commands,
+            &mut rng.cosmetic,
+            transform.translation().truncate(),
+            count as u32,
+            &emitter.spec,
+        );
     }
 }
 
 fn update_particles(
     mut commands: Commands,
     time: Res<Time>,
+    mut budget: ResMut<ParticleBudget>,
     mut particles: Query<(Entity, &mut Transform, &mut Sprite, &mut Particle)>,
 ) {
+    let mut live = 0;
     for (entity, mut transform, mut sprite, mut particle) in &mut particles {
         particle.age += time.delta_seconds();
         if particle.age >= particle.lifetime {
@@ -694,70 +2300,261 @@ fn update_particles(
             continue;
         }
 
+        live += 1;
         transform.translation += particle.velocity.extend(0.0) * time.delta_seconds();
         sprite
             .color
             .set_alpha(1.0 - particle.age / particle.lifetime);
     }
+    budget.recount(live);
+}
+
+/// Puts simulated entities back where the last step left them, undoing the
+/// blend drawn since.
+fn restore_simulated_translations(mut query: Query<(&mut Transform, &mut Interpolated)>) {
+    for (mut transform, mut interpolated) in &mut query {
+        if transform.translation != interpolated.rendered {
+            interpolated.snap(transform.translation);
+        }
+        transform.translation = interpolated.current;
+        interpolated.previous = interpolated.current;
+    }
+}
+
+fn record_simulated_translations(mut query: Query<(&Transform, &mut Interpolated)>) {
+    for (transform, mut interpolated) in &mut query {
+        if interpolated.is_added() {
+            interpolated.snap(transform.translation);
+        }
+        interpolated.current = transform.translation;
+        interpolated.rendered = transform.translation;
+    }
+}
+
+/// Draws simulated entities part way between their last two steps, by how
+/// far the clock has run into the next one.
+fn interpolate_translations(
+    time: Res<Time<Fixed>>,
+    mut query: Query<(&mut Transform, &mut Interpolated)>,
+) {
+    let blend = time.overstep_fraction();
+    for (mut transform, mut interpolated) in &mut query {
+        if transform.translation != interpolated.rendered {
+            interpolated.snap(transform.translation);
+            continue;
+        }
+        transform.translation = interpolated.previous.lerp(interpolated.current, blend);
+        interpolated.rendered = transform.translation;
+    }
+}
+
+/// Eases the camera rig toward the player, leading along their velocity and
+/// stopping where the view would run past the edge of the arena.
+fn follow_player(
+    time: Res<Time>,
+    arena: Res<Arena>,
+    player: Query<(&Transform, &PlayerVelocity), With<Player>>,
+    mut cameras: Query<(&mut CameraRig, &OrthographicProjection), With<MainCamera>>,
+) {
+    let Ok((transform, velocity)) = player.get_single() else {
+        return;
+    };
+    let Ok((mut rig, projection)) = cameras.get_single_mut() else {
+        return;
+    };
+
+    let lead = (velocity.current * rig.look_ahead).clamp_length_max(CAMERA_LOOK_AHEAD_MAX);
+    let target = transform.translation.truncate() + lead;
+    rig.focus = rig
+        .focus
+        .lerp(target, smoothing(CAMERA_SMOOTHING, time.delta_seconds()));
+
+    let travel = (arena.size() - projection.area.size()).max(Vec2::ZERO);
+    clamp_to_bounds(&mut rig.focus, travel);
 }
 
 fn apply_screen_shake(
-    mut cameras: Query<&mut Transform, With<MainCamera>>,
+    time: Res<Time>,
+    mut rng: ResMut<RunRng>,
+    mut cameras: Query<(&mut Transform, &CameraRig), With<MainCamera>>,
     mut shake: Query<&mut ScreenShake>,
 ) {
-    let Ok(mut transform) = cameras.get_single_mut() else {
+    let Ok((mut transform, rig)) = cameras.get_single_mut() else {
         return;
     };
     let Ok(mut screen_shake) = shake.get_single_mut() else {
         return;
     };
 
+    let mut offset = Vec2::ZERO;
     if screen_shake.trauma > 0.0 {
-        let mut rng = rand::thread_rng();
-        let offset = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
+        let rng = &mut rng.cosmetic;
+        offset = Vec2::new(rng.gen_range(-1.0..1.0), rng.gen_range(-1.0..1.0))
             * screen_shake.trauma
             * 6.0;
-        transform.translation.x = offset.x;
-        transform.translation.y = offset.y;
-        screen_shake.trauma = (screen_shake.trauma - screen_shake.decay * 0.016).max(0.0);
-    } else {
-        transform.translation.x = 0.0;
-        transform.translation.y = 0.0;
+        screen_shake.trauma =
+            (screen_shake.trauma - screen_shake.decay * time.delta_seconds()).max(0.0);
     }
+    transform.translation.x = rig.focus.x + offset.x;
+    transform.translation.y = rig.focus.y + offset.y;
 }
 
 fn handle_player_hit_events(
+    mut commands: Commands,
     mut events: EventReader<PlayerHitEvent>,
+    mut combo: ResMut<ComboState>,
+    mut particles: Particles,
+    mut rng: ResMut<RunRng>,
+    player: Query<&Transform, With<Player>>,
     mut shake: Query<&mut ScreenShake>,
 ) {
     if events.read().next().is_some() {
+        combo.break_chain();
+        if let Ok(transform) = player.get_single() {
+            particles.burst(
+                &mut commands,
+                &mut rng.cosmetic,
+                transform.translation.truncate(),
+                PLAYER_HIT_PARTICLE_COUNT,
+                &PLAYER_HIT_PARTICLES,
+            );
+        }
         if let Ok(mut screen_shake) = shake.get_single_mut() {
             screen_shake.trauma = (screen_shake.trauma + 0.4).min(1.0);
         }
     }
 }
 
-fn persist_currency_changes(currency: Res<Currency>) {
+fn persist_currency_changes(currency: Res<Currency>, mut store: ResMut<SaveStore>) {
     if currency.is_changed() {
-        save_currency(currency.balance);
+        store.update(|save| save.currency = currency.balance);
     }
 }
 
-fn persist_upgrade_changes(upgrades: Res<PurchasedUpgrades>) {
+fn persist_upgrade_changes(upgrades: Res<PurchasedUpgrades>, mut store: ResMut<SaveStore>) {
     if upgrades.is_changed() {
-        save_upgrades(&upgrades);
+        store.update(|save| save.upgrades = upgrades.clone());
+    }
+}
+
+fn persist_run_history(history: Res<RunHistory>, best: Res<BestRun>, mut store: ResMut<SaveStore>) {
+    if history.is_changed() {
+        store.update(|save| save.run_history = history.records.clone());
+    }
+    if best.is_changed() {
+        store.update(|save| save.best_score = best.score);
+    }
+}
+
+/// Writes coalesced save changes on the store's interval, and right away once
+/// a run has ended so the result survives an immediate quit.
+fn flush_saves(time: Res<Time>, summary: Res<RunSummary>, mut store: ResMut<SaveStore>) {
+    if summary.is_changed() {
+        store.flush();
+    } else {
+        store.tick(time.delta_seconds());
     }
 }
 
-fn reset_when_run_stops(
-    mut run_state: ResMut<RunState>,
+/// Writes anything still pending as the app shuts down, rather than trusting
+/// the store to be dropped before the process exits.
+fn flush_saves_on_exit(mut store: ResMut<SaveStore>) {
+    store.flush();
+}
+
+#[allow(clippy::too_many_arguments)]
+fn start_run(
     mut score: ResMut<Score>,
+    mut combo: ResMut<ComboState>,
+    mut run_stats: ResMut<RunStats>,
     mut health: ResMut<PlayerHealth>,
+    mut shield: ResMut<ShieldState>,
+    mut stats: ResMut<PlayerStats>,
+    mut target: ResMut<PointerTarget>,
+    mut director: ResMut<WaveDirector>,
+    mut trail_timer: ResMut<TrailSpawnTimer>,
+    seed_source: Res<SeedSource>,
+    mut rng: ResMut<RunRng>,
+    mut player: Query<(
+        &mut Transform,
+        &mut PlayerVelocity,
+        &mut Knockback,
+        &mut Player,
+        &mut TrailHistory,
+    )>,
+    mut cameras: Query<(&mut CameraRig, &mut ScreenShake)>,
 ) {
-    if !run_state.is_active() {
-        score.reset_run();
-        health.reset();
-        run_state.reset();
+    score.reset_run();
+    combo.reset();
+    *run_stats = RunStats::default();
+    health.reset();
+    shield.remaining = 0.0;
+    *stats = PlayerStats::default();
+    target.position = Vec2::ZERO;
+    *director = WaveDirector::default();
+    trail_timer.timer.reset();
+    *rng = RunRng::new(seed_source.resolve(unix_timestamp()));
+
+    if let Ok((mut transform, mut velocity, mut knockback, mut player, mut trail)) =
+        player.get_single_mut()
+    {
+        transform.translation.x = 0.0;
+        transform.translation.y = 0.0;
+        velocity.current = Vec2::ZERO;
+        knockback.velocity = Vec2::ZERO;
+        player.wave_cooldown = 0.0;
+        trail.points.clear();
+    }
+
+    if let Ok((mut rig, mut shake)) = cameras.get_single_mut() {
+        rig.focus = Vec2::ZERO;
+        shake.trauma = 0.0;
+    }
+}
+
+/// Runs as the game leaves `AppState::InGame`, before the game-over screen is
+/// built, so the summary always compares against the best run beforehand.
+#[allow(clippy::too_many_arguments)]
+fn finish_run(
+    score: Res<Score>,
+    combo: Res<ComboState>,
+    rng: Res<RunRng>,
+    mut run_stats: ResMut<RunStats>,
+    mut best: ResMut<BestRun>,
+    mut summary: ResMut<RunSummary>,
+    mut history: ResMut<RunHistory>,
+    tape: Res<RunTape>,
+) {
+    run_stats.peak_combo = run_stats.peak_combo.max(combo.peak);
+    // A replay is someone's old run; it never counts towards the records.
+    let replaying = tape.is_playing();
+    let new_best = score.current > best.score && !replaying;
+
+    *summary = RunSummary {
+        score: score.current,
+        seed: rng.seed,
+        stats: run_stats.clone(),
+        previous_best: best.clone(),
+        new_best,
+        replay: None,
+    };
+
+    if replaying {
+        return;
+    }
+
+    history.push(RunRecord {
+        timestamp: unix_timestamp(),
+        score: score.current,
+        seed: Some(rng.seed),
+        stats: run_stats.clone(),
+    });
+
+    if new_best {
+        *best = BestRun {
+            score: score.current,
+            stats: run_stats.clone(),
+        };
     }
 }
 
@@ -767,67 +2564,132 @@ fn apply_shop_purchases(
     mut currency: ResMut<Currency>,
     mut health: ResMut<PlayerHealth>,
     mut shield: ResMut<ShieldState>,
+    mut tape: ResMut<RunTape>,
 ) {
     for event in events.read() {
-        let Some(item) = SHOP_ITEMS.iter().find(|item| item.upgrade == event.item) else {
-            continue;
-        };
-        let level = match event.item {
-            UpgradeType::MovementSpeed => upgrades.movement_speed_level,
-            UpgradeType::MaxHealth => upgrades.max_health_level,
-            UpgradeType::TrailDamage => upgrades.trail_damage_level,
-            UpgradeType::ShieldDuration => upgrades.shield_level,
-        };
-
-        if level >= item.max_level {
+        // A replay makes the recorded purchases itself, on the recorded tick.
+        if tape.is_playing() {
             continue;
         }
-
-        let cost = item.cost_for_level(level);
-        if currency.balance < cost {
-            continue;
+        if purchase(
+            event.item,
+            &mut upgrades,
+            &mut currency,
+            &mut health,
+            &mut shield,
+        ) {
+            tape.record_purchase(event.item);
         }
+    }
+}
 
-        currency.balance -= cost;
+/// Buys the next level of `upgrade` if it isn't maxed out and there's
+/// currency for it. Returns whether the purchase went through.
+fn purchase(
+    upgrade: UpgradeType,
+    upgrades: &mut PurchasedUpgrades,
+    currency: &mut Currency,
+    health: &mut PlayerHealth,
+    shield: &mut ShieldState,
+) -> bool {
+    let Some(item) = SHOP_ITEMS.iter().find(|item| item.upgrade == upgrade) else {
+        return false;
+    };
+    let level = match upgrade {
+        UpgradeType::MovementSpeed => upgrades.movement_speed_level,
+        UpgradeType::MaxHealth => upgrades.max_health_level,
+        UpgradeType::TrailDamage => upgrades.trail_damage_level,
+        UpgradeType::ShieldDuration => upgrades.shield_level,
+    };
 
-        match event.item {
-            UpgradeType::MovementSpeed => {
-                upgrades.movement_speed_level += 1;
-            }
-            UpgradeType::MaxHealth => {
-                upgrades.max_health_level += 1;
-                health.max += 1;
-                health.current = health.max;
-            }
-            UpgradeType::TrailDamage => {
-                upgrades.trail_damage_level += 1;
-            }
-            UpgradeType::ShieldDuration => {
-                upgrades.shield_level += 1;
-                shield.duration += 0.75;
-                shield.remaining = shield.duration;
-            }
+    if level >= item.max_level {
+        return false;
+    }
+
+    let cost = item.cost_for_level(level);
+    if currency.balance < cost {
+        return false;
+    }
+
+    currency.balance -= cost;
+
+    match upgrade {
+        UpgradeType::MovementSpeed => {
+            upgrades.movement_speed_level += 1;
+        }
+        UpgradeType::MaxHealth => {
+            upgrades.max_health_level += 1;
+            health.max += 1;
+            health.current = health.max;
+        }
+        UpgradeType::TrailDamage => {
+            upgrades.trail_damage_level += 1;
+        }
+        UpgradeType::ShieldDuration => {
+            upgrades.shield_level += 1;
+            shield.duration += 0.75;
+            shield.remaining = shield.duration;
+        }
+    }
+    true
+}
+
+/// Picks a spawn point `ENEMY_SPAWN_DISTANCE` or so from `around`, retrying a
+/// few directions so enemies near a wall still arrive from inside the arena.
+/// When every direction is blocked, e.g. with the player in a corner, they
+/// come from the far side of the arena instead.
+fn arena_spawn_point(arena: &Arena, around: Vec2, rng: &mut impl Rng) -> Vec2 {
+    for _ in 0..8 {
+        let angle = rng.gen_range(0.0..TAU);
+        let distance = ENEMY_SPAWN_DISTANCE + rng.gen_range(-100.0..100.0);
+        let spawn = around + Vec2::from_angle(angle) * distance;
+        if arena.contains(spawn) {
+            return spawn;
         }
     }
+    let away = (-around).try_normalize().unwrap_or(Vec2::X);
+    arena.clamp(around + away * arena.size().length())
 }
 
-fn spawn_background_tiles(commands: &mut Commands, background_texture: Handle<Image>) {
-    use threadweaver_core::constants::ARENA_SIZE;
+#[allow(clippy::type_complexity)]
+fn rebuild_arena_visuals(
+    mut commands: Commands,
+    arena: Res<Arena>,
+    assets: Res<GameAssets>,
+    existing: Query<Entity, Or<(With<Background>, With<ArenaEdge>)>>,
+) {
+    for entity in &existing {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    spawn_background_tiles(&mut commands, assets.background.clone(), arena.size());
+    spawn_arena_edges(&mut commands, &arena);
+}
 
+fn spawn_background_tiles(
+    commands: &mut Commands,
+    background_texture: Handle<Image>,
+    arena_size: Vec2,
+) {
     // Tile size for background (fits within WebGPU limits)
     const TILE_SIZE: f32 = 512.0;
     // Calculate how many tiles we need to cover the arena
-    let tiles_per_side = ((ARENA_SIZE / TILE_SIZE).ceil() as i32) + 1;
-    let total_tiles = tiles_per_side * tiles_per_side;
+    let tiles_x = ((arena_size.x / TILE_SIZE).ceil() as i32) + 1;
+    let tiles_y = ((arena_size.y / TILE_SIZE).ceil() as i32) + 1;
 
-    info!("Spawning {} background tiles ({}/side)", total_tiles, tiles_per_side);
+    info!(
+        "Spawning {} background tiles ({}x{})",
+        tiles_x * tiles_y,
+        tiles_x,
+        tiles_y
+    );
 
     // Calculate starting position to center the grid
-    let start_x = -(tiles_per_side as f32 / 2.0) * TILE_SIZE;
-    let start_y = -(tiles_per_side as f32 / 2.0) * TILE_SIZE;
+    let start_x = -(tiles_x as f32 / 2.0) * TILE_SIZE;
+    let start_y = -(tiles_y as f32 / 2.0) * TILE_SIZE;
 
-    for y in 0..tiles_per_side {
-        for x in 0..tiles_per_side {
+    for y in 0..tiles_y {
+        for x in 0..tiles_x {
             let pos_x = start_x + (x as f32) * TILE_SIZE;
             let pos_y = start_y + (y as f32) * TILE_SIZE;
 
@@ -846,3 +2708,51 @@ fn spawn_background_tiles(commands: &mut Commands, background_texture: Handle<Im
         }
     }
 }
+
+/// Outlines the arena wall with plain sprites: one bar per side for a
+/// rectangle, short rotated segments around a circle.
+fn spawn_arena_edges(commands: &mut Commands, arena: &Arena) {
+    let color = Color::srgba(0.55, 0.85, 1.0, 0.6);
+    let mut spawn_bar = |center: Vec2, size: Vec2, rotation: f32| {
+        commands.spawn((
+            SpriteBundle {
+                transform: Transform::from_translation(center.extend(-50.0))
+                    .with_rotation(Quat::from_rotation_z(rotation)),
+                sprite: Sprite {
+                    color,
+                    custom_size: Some(size),
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            ArenaEdge,
+        ));
+    };
+
+    match arena.shape {
+        ArenaShape::Rect { size } => {
+            let half = size * 0.5;
+            let horizontal = Vec2::new(size.x + ARENA_EDGE_THICKNESS, ARENA_EDGE_THICKNESS);
+            let vertical = Vec2::new(ARENA_EDGE_THICKNESS, size.y + ARENA_EDGE_THICKNESS);
+            spawn_bar(Vec2::new(0.0, half.y), horizontal, 0.0);
+            spawn_bar(Vec2::new(0.0, -half.y), horizontal, 0.0);
+            spawn_bar(Vec2::new(half.x, 0.0), vertical, 0.0);
+            spawn_bar(Vec2::new(-half.x, 0.0), vertical, 0.0);
+        }
+        ArenaShape::Circle { radius } => {
+            const SEGMENT_LENGTH: f32 = 32.0;
+            let segments = ((TAU * radius / SEGMENT_LENGTH).ceil() as u32).max(8);
+            let step = TAU / segments as f32;
+            // Slightly overlong chords so neighbouring segments meet.
+            let chord = 2.0 * radius * (step * 0.5).sin() + ARENA_EDGE_THICKNESS * 0.5;
+            for i in 0..segments {
+                let angle = i as f32 * step;
+                spawn_bar(
+                    Vec2::from_angle(angle) * radius,
+                    Vec2::new(ARENA_EDGE_THICKNESS, chord),
+                    angle,
+                );
+            }
+        }
+    }
+}
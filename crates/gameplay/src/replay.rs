@@ -0,0 +1,381 @@
+use super::{apply_upgrades, purchase};
+use bevy::prelude::*;
+use threadweaver_core::prelude::*;
+use threadweaver_platform::{ReplayLoader, SaveStore, LATEST_REPLAY};
+
+/// The run's input, one entry per simulation step: either being recorded
+/// from the live input systems or fed back from a replay in their place.
+#[derive(Resource)]
+pub(crate) enum RunTape {
+    Recording(Replay),
+    Playing {
+        replay: Replay,
+        targets: Vec<Vec2>,
+        tick: u32,
+    },
+}
+
+impl Default for RunTape {
+    fn default() -> Self {
+        Self::Recording(Replay::default())
+    }
+}
+
+impl RunTape {
+    pub(crate) fn is_playing(&self) -> bool {
+        matches!(self, Self::Playing { .. })
+    }
+
+    /// Whether a replay has run out of recorded input.
+    pub(crate) fn is_finished(&self) -> bool {
+        matches!(self, Self::Playing { targets, tick, .. } if *tick as usize >= targets.len())
+    }
+
+    pub(crate) fn record_purchase(&mut self, upgrade: UpgradeType) {
+        if let Self::Recording(replay) = self {
+            replay.purchases.push((replay.ticks, upgrade));
+        }
+    }
+}
+
+/// A loaded replay waiting to be played as the next run.
+#[derive(Resource)]
+pub(crate) struct QueuedReplay(Replay);
+
+pub(crate) fn replaying(tape: Res<RunTape>) -> bool {
+    tape.is_playing()
+}
+
+/// Stands in for `prime_persistence` when the launcher asked for a replay:
+/// waits for the save, then for the replay, and queues it up.
+pub(crate) fn load_requested_replay(
+    mut commands: Commands,
+    mut store: ResMut<SaveStore>,
+    mut loader: ResMut<ReplayLoader>,
+    catalog: Res<EnemyCatalog>,
+) {
+    // The web build reads replays from whichever storage the save settled in.
+    if !store.poll_loaded() {
+        return;
+    }
+    let Some(replay) = loader.poll(&store) else {
+        return;
+    };
+    commands.remove_resource::<ReplayLoader>();
+    match replay {
+        Some(replay) if replay.is_compatible(&catalog) => {
+            commands.insert_resource(QueuedReplay(replay))
+        }
+        Some(_) => warn!(
+            "replay \"{}\" was recorded by an incompatible build or enemy catalog",
+            loader.name()
+        ),
+        None => warn!("no readable replay named \"{}\"", loader.name()),
+    }
+}
+
+pub(crate) fn start_queued_replay(mut next_state: ResMut<NextState<AppState>>) {
+    next_state.set(AppState::InGame);
+}
+
+/// Runs straight after `start_run`. A queued replay puts back the seed,
+/// currency, upgrades and weapon it was recorded with; otherwise recording
+/// starts from the run as it stands.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn begin_tape(
+    mut commands: Commands,
+    queued: Option<Res<QueuedReplay>>,
+    mut tape: ResMut<RunTape>,
+    mut rng: ResMut<RunRng>,
+    mut currency: ResMut<Currency>,
+    mut upgrades: ResMut<PurchasedUpgrades>,
+    mut health: ResMut<PlayerHealth>,
+    mut shield: ResMut<ShieldState>,
+    mut player: Query<&mut Player>,
+    catalog: Res<EnemyCatalog>,
+) {
+    let mut player = player.get_single_mut().ok();
+    if let Some(queued) = queued {
+        commands.remove_resource::<QueuedReplay>();
+        let replay = queued.0.clone();
+        *rng = RunRng::new(replay.seed);
+        currency.balance = replay.currency;
+        *upgrades = replay.upgrades.clone();
+        if let Some(player) = player.as_mut() {
+            player.weapon = replay.weapon;
+        }
+        *tape = RunTape::Playing {
+            targets: replay.pointer_targets(),
+            replay,
+            tick: 0,
+        };
+    } else {
+        let weapon = player.map_or(WeaponType::Trail, |player| player.weapon);
+        *tape = RunTape::Recording(Replay::new(
+            rng.seed,
+            catalog.fingerprint(),
+            currency.balance,
+            upgrades.clone(),
+            weapon,
+        ));
+    }
+    apply_upgrades(&upgrades, &mut health, &mut shield);
+}
+
+/// First step of every simulation tick: records the input the tick is about
+/// to use, or overwrites it with the replay's.
+pub(crate) fn run_tape(
+    mut tape: ResMut<RunTape>,
+    mut target: ResMut<PointerTarget>,
+    mut switch: ResMut<WeaponSwitch>,
+    mut upgrades: ResMut<PurchasedUpgrades>,
+    mut currency: ResMut<Currency>,
+    mut health: ResMut<PlayerHealth>,
+    mut shield: ResMut<ShieldState>,
+) {
+    match &mut *tape {
+        RunTape::Recording(replay) => replay.record_tick(target.position, switch.requested),
+        RunTape::Playing {
+            replay,
+            targets,
+            tick,
+        } => {
+            let Some(&position) = targets.get(*tick as usize) else {
+                return;
+            };
+            target.position = position;
+            switch.requested = replay.weapon_switches.binary_search(tick).is_ok();
+            for &(_, upgrade) in replay.purchases.iter().filter(|(at, _)| at == tick) {
+                purchase(
+                    upgrade,
+                    &mut upgrades,
+                    &mut currency,
+                    &mut health,
+                    &mut shield,
+                );
+            }
+            *tick += 1;
+        }
+    }
+}
+
+/// A replay that runs out of input before the player dies ends the run
+/// where the recording stopped.
+pub(crate) fn end_finished_replay(tape: Res<RunTape>, mut next_state: ResMut<NextState<AppState>>) {
+    if tape.is_finished() {
+        next_state.set(AppState::GameOver);
+    }
+}
+
+/// Runs after `finish_run`. Stores the finished recording as the latest
+/// replay, or checks a replay reached the score it was recorded with, for
+/// the game-over screen, and puts the player's own currency and upgrades
+/// back.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn end_tape(
+    score: Res<Score>,
+    store: Res<SaveStore>,
+    mut summary: ResMut<RunSummary>,
+    mut tape: ResMut<RunTape>,
+    mut currency: ResMut<Currency>,
+    mut upgrades: ResMut<PurchasedUpgrades>,
+    mut health: ResMut<PlayerHealth>,
+    mut shield: ResMut<ShieldState>,
+) {
+    match std::mem::take(&mut *tape) {
+        RunTape::Recording(mut replay) => {
+            replay.score = score.current;
+            if let Err(error) = store.store_replay(LATEST_REPLAY, &replay) {
+                warn!("failed to store replay: {error}");
+            }
+        }
+        RunTape::Playing { replay, .. } => {
+            let check = ReplayCheck {
+                recorded: replay.score,
+                reached: score.current,
+            };
+            if check.diverged() {
+                warn!(
+                    "replay diverged: finished on {} but was recorded on {}",
+                    check.reached, check.recorded
+                );
+            } else {
+                info!(
+                    "replay finished on its recorded score of {}",
+                    check.recorded
+                );
+            }
+            summary.replay = Some(check);
+            let save = store.data();
+            currency.balance = save.currency;
+            *upgrades = save.upgrades.clone();
+            apply_upgrades(&upgrades, &mut health, &mut shield);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{GameplayPlugin, ShopPurchaseEvent};
+    use bevy::state::app::StatesPlugin;
+    use bevy::time::TimeUpdateStrategy;
+    use std::time::Duration;
+    use threadweaver_platform::{MemoryBackend, SaveBackend, SaveData};
+
+    const RECORDED_FRAMES: u32 = 900;
+
+    fn headless(backend: &MemoryBackend) -> App {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            AssetPlugin {
+                file_path: "../../assets".into(),
+                ..default()
+            },
+            StatesPlugin,
+            bevy::input::InputPlugin,
+            WindowPlugin {
+                primary_window: None,
+                ..default()
+            },
+        ))
+        .init_asset::<Image>()
+        .init_asset::<Font>()
+        .init_asset::<Mesh>()
+        .init_asset::<ColorMaterial>()
+        .insert_resource(SaveStore::new(backend.clone()))
+        .add_plugins(GameplayPlugin)
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_millis(
+            16,
+        )));
+        app
+    }
+
+    fn state(app: &App) -> AppState {
+        *app.world().resource::<State<AppState>>().get()
+    }
+
+    fn update_until(app: &mut App, wanted: AppState) {
+        for _ in 0..10_000 {
+            if state(app) == wanted {
+                return;
+            }
+            app.update();
+        }
+        panic!("never reached {wanted:?}");
+    }
+
+    /// Plays a run with scripted steering, a couple of weapon switches and
+    /// some shop purchases, then ends it. Returns what it stored and how it
+    /// went.
+    fn record_run() -> (Replay, u32, RunStats) {
+        let backend = MemoryBackend::with_save(SaveData {
+            currency: 2_000,
+            ..default()
+        });
+        let mut app = headless(&backend);
+        update_until(&mut app, AppState::Title);
+        app.insert_resource(SeedSource::Fixed(7));
+        app.world_mut()
+            .resource_mut::<NextState<AppState>>()
+            .set(AppState::InGame);
+        app.update();
+
+        for frame in 0..RECORDED_FRAMES {
+            if state(&app) != AppState::InGame {
+                break;
+            }
+            let t = frame as f32 * 0.03;
+            let radius = 150.0 + 100.0 * (t * 0.37).sin();
+            app.world_mut().resource_mut::<PointerTarget>().position = Vec2::from_angle(t) * radius;
+            if frame == 200 || frame == 500 {
+                app.world_mut().resource_mut::<WeaponSwitch>().requested = true;
+            }
+            if frame == 300 {
+                app.world_mut().send_event(ShopPurchaseEvent {
+                    item: UpgradeType::MaxHealth,
+                });
+                app.world_mut().send_event(ShopPurchaseEvent {
+                    item: UpgradeType::TrailDamage,
+                });
+            }
+            app.update();
+        }
+        if state(&app) == AppState::InGame {
+            app.world_mut()
+                .resource_mut::<NextState<AppState>>()
+                .set(AppState::GameOver);
+        }
+        update_until(&mut app, AppState::GameOver);
+
+        let replay = backend.replay(LATEST_REPLAY).expect("run was stored");
+        let score = app.world().resource::<Score>().current;
+        let stats = app.world().resource::<RunStats>().clone();
+        (replay, score, stats)
+    }
+
+    /// Plays `replay` on a fresh save, asked for the way the launcher does
+    /// for `--replay`, through to the game-over screen.
+    fn play_back(replay: &Replay, backend: &MemoryBackend) -> App {
+        backend.store_replay(LATEST_REPLAY, replay).unwrap();
+        let mut app = headless(backend);
+        app.insert_resource(ReplayLoader::from_args(&["--replay".into()]).unwrap());
+        update_until(&mut app, AppState::GameOver);
+        app
+    }
+
+    #[test]
+    fn replay_ends_on_the_recorded_score() {
+        let (replay, score, stats) = record_run();
+        assert_eq!(replay.score, score);
+        assert_eq!(replay.weapon_switches.len(), 2);
+        assert_eq!(replay.purchases.len(), 2);
+        assert!(score > 0, "the scripted run should score");
+
+        // A different save and seed source, which the replay must override.
+        let backend = MemoryBackend::with_save(SaveData {
+            currency: 3,
+            ..default()
+        });
+        let app = play_back(&replay, &backend);
+
+        assert_eq!(app.world().resource::<Score>().current, score);
+        assert_eq!(*app.world().resource::<RunStats>(), stats);
+        let check = app.world().resource::<RunSummary>().replay;
+        assert_eq!(
+            check,
+            Some(ReplayCheck {
+                recorded: score,
+                reached: score,
+            })
+        );
+        // Playback leaves the player's own save and records alone.
+        assert_eq!(app.world().resource::<Currency>().balance, 3);
+        assert_eq!(backend.replay(LATEST_REPLAY), Some(replay.clone()));
+
+        // A recording that claims another score is reported as diverged.
+        let mut misrecorded = replay;
+        misrecorded.score += 1;
+        let app = play_back(&misrecorded, &MemoryBackend::new());
+        let check = app.world().resource::<RunSummary>().replay;
+        assert!(check.is_some_and(|check| check.diverged()));
+    }
+
+    #[test]
+    fn replays_of_other_catalogs_are_not_played() {
+        let backend = MemoryBackend::new();
+        backend
+            .store_replay(LATEST_REPLAY, &Replay::default())
+            .unwrap();
+        let mut app = headless(&backend);
+        app.insert_resource(ReplayLoader::from_args(&["--replay".into()]).unwrap());
+        update_until(&mut app, AppState::Title);
+        for _ in 0..10 {
+            app.update();
+        }
+
+        assert_eq!(state(&app), AppState::Title);
+        assert!(!app.world().contains_resource::<QueuedReplay>());
+    }
+}
@@ -0,0 +1,170 @@
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::marker::PhantomData;
+use threadweaver_core::hitbox::Shape;
+
+/// Width of one grid cell in world units. Roughly the size of the largest
+/// regular enemy plus the trail's reach, so most queries touch a handful of
+/// cells.
+pub const GRID_CELL_SIZE: f32 = 128.0;
+
+/// One indexed hitbox.
+#[derive(Clone, Copy, Debug)]
+pub struct GridEntry {
+    pub entity: Entity,
+    pub shape: Shape,
+}
+
+/// Uniform-grid broad phase over every entity with `T`, rebuilt each frame.
+/// Entries live in the cell holding their centre; queries widen by the
+/// largest extent indexed so big entities like bosses are still found.
+#[derive(Resource)]
+pub struct SpatialGrid<T> {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<usize>>,
+    entries: Vec<GridEntry>,
+    max_extent: Vec2,
+    marker: PhantomData<fn() -> T>,
+}
+
+impl<T> Default for SpatialGrid<T> {
+    fn default() -> Self {
+        Self::new(GRID_CELL_SIZE)
+    }
+}
+
+impl<T> SpatialGrid<T> {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            entries: Vec::new(),
+            max_extent: Vec2::ZERO,
+            marker: PhantomData,
+        }
+    }
+
+    /// Empties the grid, keeping its allocations for the next rebuild.
+    pub fn clear(&mut self) {
+        for cell in self.cells.values_mut() {
+            cell.clear();
+        }
+        self.entries.clear();
+        self.max_extent = Vec2::ZERO;
+    }
+
+    pub fn insert(&mut self, entity: Entity, shape: Shape) {
+        let (min, max) = shape.bounds();
+        let center = (min + max) * 0.5;
+        let index = self.entries.len();
+        self.entries.push(GridEntry { entity, shape });
+        self.max_extent = self.max_extent.max((max - min) * 0.5);
+        self.cells
+            .entry(self.cell_of(center))
+            .or_default()
+            .push(index);
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Entries whose hitbox overlaps `shape`.
+    pub fn query(&self, shape: Shape) -> impl Iterator<Item = &GridEntry> {
+        let (min, max) = shape.bounds();
+        let min = self.cell_of(min - self.max_extent);
+        let max = self.cell_of(max + self.max_extent);
+        (min.y..=max.y)
+            .flat_map(move |y| (min.x..=max.x).map(move |x| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .map(|&index| &self.entries[index])
+            .filter(move |entry| entry.shape.overlaps(&shape))
+    }
+
+    fn cell_of(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    struct Marker;
+
+    fn found(grid: &SpatialGrid<Marker>, shape: Shape) -> Vec<Entity> {
+        let mut entities: Vec<Entity> = grid.query(shape).map(|entry| entry.entity).collect();
+        entities.sort();
+        entities
+    }
+
+    fn random_shape(rng: &mut StdRng, max_size: f32) -> Shape {
+        let center = Vec2::new(rng.gen_range(-800.0..800.0), rng.gen_range(-800.0..800.0));
+        let size = rng.gen_range(1.0..max_size);
+        match rng.gen_range(0..3) {
+            0 => Shape::circle(center, size),
+            1 => Shape::aabb(center, Vec2::new(size, rng.gen_range(1.0..max_size))),
+            _ => {
+                let axis = Vec2::from_angle(rng.gen_range(0.0..std::f32::consts::TAU)) * size;
+                Shape::capsule(center - axis, center + axis, rng.gen_range(1.0..20.0))
+            }
+        }
+    }
+
+    #[test]
+    fn large_entries_are_found_from_neighbouring_cells() {
+        let mut grid = SpatialGrid::<Marker>::new(GRID_CELL_SIZE);
+        let boss = Entity::from_raw(1);
+        grid.insert(boss, Shape::circle(Vec2::ZERO, GRID_CELL_SIZE * 2.5));
+        grid.insert(Entity::from_raw(2), Shape::circle(Vec2::splat(10.0), 4.0));
+
+        // Two cells over from the boss's centre but inside its reach.
+        let probe = Shape::circle(Vec2::new(GRID_CELL_SIZE * 2.2, 0.0), 2.0);
+        assert_eq!(found(&grid, probe), [boss]);
+    }
+
+    #[test]
+    fn queries_match_a_brute_force_scan() {
+        let mut rng = StdRng::seed_from_u64(21);
+        for round in 0..20 {
+            let mut grid = SpatialGrid::<Marker>::new(GRID_CELL_SIZE);
+            let mut shapes = Vec::new();
+            for index in 0..200 {
+                // A few boss-sized entries per round among regular ones.
+                let max_size = if index % 50 == 0 { 400.0 } else { 40.0 };
+                let shape = random_shape(&mut rng, max_size);
+                grid.insert(Entity::from_raw(index), shape);
+                shapes.push(shape);
+            }
+
+            for _ in 0..100 {
+                let query = random_shape(&mut rng, 120.0);
+                let expected: Vec<Entity> = shapes
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, shape)| shape.overlaps(&query))
+                    .map(|(index, _)| Entity::from_raw(index as u32))
+                    .collect();
+                assert_eq!(found(&grid, query), expected, "round {round}, {query:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn clearing_forgets_entries_and_extent() {
+        let mut grid = SpatialGrid::<Marker>::new(GRID_CELL_SIZE);
+        grid.insert(Entity::from_raw(1), Shape::circle(Vec2::ZERO, 500.0));
+        grid.clear();
+        grid.insert(Entity::from_raw(2), Shape::circle(Vec2::ZERO, 1.0));
+
+        assert_eq!(grid.len(), 1);
+        assert!(found(&grid, Shape::circle(Vec2::new(400.0, 0.0), 1.0)).is_empty());
+    }
+}
@@ -7,7 +7,9 @@ use bevy::render::{
     RenderPlugin,
 };
 use bevy::window::WindowPlugin;
+use threadweaver_core::resources::SeedSource;
 use threadweaver_gameplay::GameplayPlugin;
+use threadweaver_platform::ReplayLoader;
 use threadweaver_ui::ThreadweaverUiPlugin;
 
 #[cfg(target_arch = "wasm32")]
@@ -27,9 +29,14 @@ fn main() {
         Backends::PRIMARY | Backends::SECONDARY
     };
 
+    let args = threadweaver_platform::launch_args();
     app.insert_resource(ClearColor(Color::srgba(0.01, 0.01, 0.015, 1.0)))
         .add_plugins(default_plugins(backends))
-        .add_plugins((GameplayPlugin, ThreadweaverUiPlugin));
+        .add_plugins((GameplayPlugin, ThreadweaverUiPlugin))
+        .insert_resource(seed_source(&args));
+    if let Some(loader) = ReplayLoader::from_args(&args) {
+        app.insert_resource(loader);
+    }
 
     #[cfg(target_arch = "wasm32")]
     {
@@ -39,6 +46,24 @@ fn main() {
     app.run();
 }
 
+/// `--seed <n>` replays a run's seed and `--daily` plays today's challenge;
+/// otherwise every run gets a fresh seed.
+fn seed_source(args: &[String]) -> SeedSource {
+    let mut source = SeedSource::Random;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--daily" => source = SeedSource::Daily,
+            "--seed" => match args.next().and_then(|seed| seed.parse().ok()) {
+                Some(seed) => source = SeedSource::Fixed(seed),
+                None => warn!("--seed expects a whole number; using a random seed"),
+            },
+            _ => {}
+        }
+    }
+    source
+}
+
 fn default_plugins(backends: Backends) -> PluginGroupBuilder {
     let window = Window {
         title: "Threadweaver".into(),
@@ -49,7 +74,7 @@ fn default_plugins(backends: Backends) -> PluginGroupBuilder {
         ..Default::default()
     };
 
-    let mut plugins = DefaultPlugins
+    let plugins = DefaultPlugins
         .set(WindowPlugin {
             primary_window: Some(window),
             ..Default::default()
@@ -70,9 +95,7 @@ fn default_plugins(backends: Backends) -> PluginGroupBuilder {
         .build();
 
     #[cfg(target_arch = "wasm32")]
-    {
-        plugins = plugins.disable::<bevy::log::LogPlugin>();
-    }
+    let plugins = plugins.disable::<bevy::log::LogPlugin>();
 
     plugins
 }
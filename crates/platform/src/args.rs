@@ -0,0 +1,91 @@
+/// Options this launch was started with, in command-line form. Native
+/// builds read the process arguments; the web build reads the page's query
+/// string, so `?seed=42&daily` arrives as `--seed 42 --daily`.
+pub fn launch_args() -> Vec<String> {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        std::env::args().skip(1).collect()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        web_sys::window()
+            .and_then(|window| window.location().search().ok())
+            .map(|search| query_args(&search))
+            .unwrap_or_default()
+    }
+}
+
+/// Turns a URL query string into launch args. Keys and values are
+/// percent-decoded, with `+` read as a space, as browsers encode forms.
+pub fn query_args(query: &str) -> Vec<String> {
+    query
+        .trim_start_matches('?')
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .flat_map(|pair| {
+            let (key, value) = match pair.split_once('=') {
+                Some((key, value)) => (key, Some(percent_decode(value))),
+                None => (pair, None),
+            };
+            std::iter::once(format!("--{}", percent_decode(key))).chain(value)
+        })
+        .collect()
+}
+
+fn percent_decode(raw: &str) -> String {
+    let mut bytes = Vec::with_capacity(raw.len());
+    let mut rest = raw.as_bytes();
+    while let Some((&byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let escaped = rest
+                    .get(..2)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                match escaped {
+                    Some(decoded) => {
+                        bytes.push(decoded);
+                        rest = &rest[2..];
+                    }
+                    // A stray `%` is kept as typed.
+                    None => bytes.push(b'%'),
+                }
+            }
+            _ => bytes.push(byte),
+        }
+    }
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pairs_and_flags_become_args() {
+        assert_eq!(
+            query_args("?seed=42&daily&replay=latest"),
+            ["--seed", "42", "--daily", "--replay", "latest"]
+        );
+        assert!(query_args("").is_empty());
+        assert!(query_args("?").is_empty());
+    }
+
+    #[test]
+    fn values_are_percent_decoded() {
+        assert_eq!(
+            query_args("replay=my%20run&name=caf%C3%A9+au+lait"),
+            ["--replay", "my run", "--name", "café au lait"]
+        );
+    }
+
+    #[test]
+    fn malformed_escapes_are_kept() {
+        assert_eq!(
+            query_args("replay=100%&x=%zz%4"),
+            ["--replay", "100%", "--x", "%zz%4"]
+        );
+    }
+}
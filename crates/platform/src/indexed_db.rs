@@ -0,0 +1,390 @@
+use crate::replay;
+use crate::save::SaveData;
+use crate::storage::{LocalStorageBackend, ReplayCallback, SaveBackend, SaveError, StandInBackend};
+use std::cell::RefCell;
+use std::sync::{Arc, Mutex};
+use threadweaver_core::replay::Replay;
+use wasm_bindgen::closure::Closure;
+use wasm_bindgen::{JsCast, JsValue};
+use web_sys::{IdbDatabase, IdbOpenDbRequest, IdbRequest, IdbTransactionMode};
+
+const DATABASE_NAME: &str = "threadweaver";
+const DATABASE_VERSION: u32 = 1;
+const STORE_NAME: &str = "saves";
+const SAVE_KEY: &str = "save";
+
+thread_local! {
+    // Browser handles aren't `Send`, so the open database lives here rather
+    // than inside the backend resource.
+    static DATABASE: RefCell<Option<IdbDatabase>> = const { RefCell::new(None) };
+}
+
+#[derive(Default, PartialEq)]
+enum Phase {
+    #[default]
+    Opening,
+    Ready,
+    Unavailable,
+}
+
+#[derive(Default)]
+struct Shared {
+    phase: Phase,
+    save: Option<SaveData>,
+    /// Latest write issued before the database opened.
+    pending: Option<String>,
+    /// A save transaction that failed after `store` returned.
+    failed_write: Option<SaveError>,
+    /// Set when the database may hold a save this session couldn't read.
+    stand_in: Option<StandInBackend<LocalStorageBackend>>,
+}
+
+impl Shared {
+    /// Where the save goes once the database is out of the picture.
+    fn fallback(&self) -> &dyn SaveBackend {
+        match &self.stand_in {
+            Some(stand_in) => stand_in,
+            None => &LocalStorageBackend,
+        }
+    }
+}
+
+/// Persists the save in an IndexedDB object store.
+///
+/// Opening and the first read are asynchronous; `is_ready` stays `false`
+/// until they finish. Writes are queued as transactions and never block the
+/// frame. A browser that refuses the database falls back to `localStorage`.
+pub struct IndexedDbBackend {
+    shared: Arc<Mutex<Shared>>,
+}
+
+impl IndexedDbBackend {
+    /// Starts opening the database. `None` when IndexedDB isn't available at
+    /// all, e.g. some private browsing modes.
+    pub fn open() -> Option<Self> {
+        let factory = web_sys::window()?.indexed_db().ok().flatten()?;
+        let request = factory
+            .open_with_u32(DATABASE_NAME, DATABASE_VERSION)
+            .ok()?;
+        let shared = Arc::new(Mutex::new(Shared::default()));
+
+        let upgrade_request = request.clone();
+        request.set_onupgradeneeded(Some(
+            Closure::once_into_js(move || {
+                if let Some(database) = open_result(&upgrade_request) {
+                    let _ = database.create_object_store(STORE_NAME);
+                }
+            })
+            .unchecked_ref(),
+        ));
+
+        let success_request = request.clone();
+        let success_shared = shared.clone();
+        request.set_onsuccess(Some(
+            Closure::once_into_js(move || match open_result(&success_request) {
+                // A blocked open can still succeed later, after the game has
+                // settled on localStorage; stay there.
+                Some(database) if has_fallen_back(&success_shared) => database.close(),
+                Some(database) => {
+                    DATABASE.with(|slot| *slot.borrow_mut() = Some(database));
+                    read_save(success_shared);
+                }
+                None => fall_back(&success_shared, Fallback::Unopened),
+            })
+            .unchecked_ref(),
+        ));
+
+        let error_shared = shared.clone();
+        request.set_onerror(Some(
+            Closure::once_into_js(move || fall_back(&error_shared, Fallback::Unopened))
+                .unchecked_ref(),
+        ));
+
+        // Another tab holding an older version open blocks the upgrade; don't
+        // wait on it with the game stuck loading. The database exists, so it
+        // may well hold the save.
+        let blocked_shared = shared.clone();
+        request.set_onblocked(Some(
+            Closure::once_into_js(move || fall_back(&blocked_shared, Fallback::Unread))
+                .unchecked_ref(),
+        ));
+
+        Some(Self { shared })
+    }
+}
+
+impl SaveBackend for IndexedDbBackend {
+    fn load(&self) -> Option<SaveData> {
+        let shared = self.shared.lock().ok()?;
+        match shared.phase {
+            Phase::Ready => shared.save.clone(),
+            Phase::Unavailable => shared.fallback().load(),
+            Phase::Opening => None,
+        }
+    }
+
+    fn store(&self, save: &SaveData) -> Result<(), SaveError> {
+        let Ok(mut shared) = self.shared.lock() else {
+            return Err(SaveError::Browser("save state is poisoned".into()));
+        };
+        match shared.phase {
+            Phase::Ready => {
+                shared.save = Some(save.clone());
+                drop(shared);
+                put(
+                    SAVE_KEY,
+                    &save.to_json(),
+                    || {},
+                    record_failure(&self.shared),
+                )
+            }
+            Phase::Unavailable => shared.fallback().store(save),
+            Phase::Opening => {
+                shared.pending = Some(save.to_json());
+                Ok(())
+            }
+        }
+    }
+
+    fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+        let Ok(shared) = self.shared.lock() else {
+            return Err(SaveError::Browser("save state is poisoned".into()));
+        };
+        match shared.phase {
+            Phase::Ready => put(
+                &replay::storage_key(name),
+                &replay::encode(replay),
+                || {},
+                || bevy::log::warn!("IndexedDB replay transaction failed"),
+            ),
+            Phase::Unavailable => shared.fallback().store_replay(name, replay),
+            Phase::Opening => Err(SaveError::Browser("IndexedDB is still opening".into())),
+        }
+    }
+
+    fn load_replay(&self, name: &str, on_loaded: ReplayCallback) {
+        let Ok(shared) = self.shared.lock() else {
+            return on_loaded(None);
+        };
+        if shared.phase != Phase::Ready {
+            return shared.fallback().load_replay(name, on_loaded);
+        }
+        drop(shared);
+        // Exactly one of the read's callbacks runs, and neither does when the
+        // read can't be issued, so they share `on_loaded` through a slot.
+        let slot = Arc::new(Mutex::new(Some(on_loaded)));
+        let take = |slot: &Mutex<Option<ReplayCallback>>| slot.lock().ok()?.take();
+        let (value_slot, error_slot) = (slot.clone(), slot.clone());
+        let requested = get(
+            &replay::storage_key(name),
+            move |json| {
+                if let Some(on_loaded) = take(&value_slot) {
+                    on_loaded(json.and_then(|json| replay::decode(&json)));
+                }
+            },
+            move || {
+                if let Some(on_loaded) = take(&error_slot) {
+                    on_loaded(None);
+                }
+            },
+        );
+        if !requested {
+            if let Some(on_loaded) = take(&slot) {
+                LocalStorageBackend.load_replay(name, on_loaded);
+            }
+        }
+    }
+
+    fn take_failed_write(&self) -> Option<SaveError> {
+        self.shared.lock().ok()?.failed_write.take()
+    }
+
+    fn is_ready(&self) -> bool {
+        self.shared
+            .lock()
+            .map(|shared| shared.phase != Phase::Opening)
+            .unwrap_or(true)
+    }
+}
+
+fn open_result(request: &IdbOpenDbRequest) -> Option<IdbDatabase> {
+    request.result().ok()?.dyn_into().ok()
+}
+
+fn has_fallen_back(shared: &Mutex<Shared>) -> bool {
+    shared
+        .lock()
+        .map_or(true, |shared| shared.phase == Phase::Unavailable)
+}
+
+/// Notes a save transaction that failed once queued, so the `SaveStore`
+/// writes again instead of taking it as saved.
+fn record_failure(shared: &Arc<Mutex<Shared>>) -> impl FnOnce() + 'static {
+    let shared = shared.clone();
+    move || {
+        if let Ok(mut shared) = shared.lock() {
+            shared.failed_write = Some(SaveError::Browser(
+                "IndexedDB save transaction failed".into(),
+            ));
+        }
+    }
+}
+
+/// Why IndexedDB was given up on.
+enum Fallback {
+    /// The browser wouldn't open it at all.
+    Unopened,
+    /// It's there, and may hold the save, but that couldn't be read.
+    Unread,
+}
+
+/// Gives up on IndexedDB for this session and moves to `localStorage`. When
+/// the save there went unread, a `localStorage` without a save of its own
+/// takes no writes, or the next session would find this one's defaults.
+fn fall_back(shared: &Mutex<Shared>, reason: Fallback) {
+    let Ok(mut shared) = shared.lock() else {
+        return;
+    };
+    if shared.phase == Phase::Ready {
+        return;
+    }
+    shared.phase = Phase::Unavailable;
+    if let Fallback::Unread = reason {
+        shared.stand_in = Some(StandInBackend::new(LocalStorageBackend));
+    }
+    // Replays follow the save, so nothing should read the database now.
+    if let Some(database) = DATABASE.with(|slot| slot.borrow_mut().take()) {
+        database.close();
+    }
+    if let Some(save) = shared
+        .pending
+        .take()
+        .and_then(|json| SaveData::from_json(&json))
+    {
+        if let Err(error) = shared.fallback().store(&save) {
+            bevy::log::warn!("failed to write save: {error}");
+        }
+    }
+}
+
+fn read_save(shared: Arc<Mutex<Shared>>) {
+    let value_shared = shared.clone();
+    let error_shared = shared.clone();
+    // A failed read says nothing about what's stored, so it mustn't look
+    // like an empty database: the migration below would then overwrite the
+    // real save with the localStorage copy.
+    let requested = get(
+        SAVE_KEY,
+        move |json| finish_load(&value_shared, json),
+        move || fall_back(&error_shared, Fallback::Unread),
+    );
+    if !requested {
+        fall_back(&shared, Fallback::Unread);
+    }
+}
+
+fn finish_load(shared: &Arc<Mutex<Shared>>, json: Option<String>) {
+    let stored = match json {
+        Some(json) => match SaveData::from_json(&json) {
+            Some(save) => Some(save),
+            // Keep an unreadable save untouched rather than write over it.
+            None => return fall_back(shared, Fallback::Unread),
+        },
+        None => None,
+    };
+    // Nothing in IndexedDB yet: carry over whatever an older build left in
+    // localStorage, including the pre-versioned per-value keys.
+    let migrated = match stored {
+        Some(_) => None,
+        None => LocalStorageBackend.load(),
+    };
+
+    let Ok(mut state) = shared.lock() else {
+        return;
+    };
+    state.phase = Phase::Ready;
+    state.save = stored.or_else(|| migrated.clone());
+    let pending = state.pending.take();
+    drop(state);
+
+    let written = match (pending, migrated) {
+        (Some(json), _) => put(SAVE_KEY, &json, || {}, record_failure(shared)),
+        (None, Some(save)) => put(
+            SAVE_KEY,
+            &save.to_json(),
+            || LocalStorageBackend.clear(),
+            record_failure(shared),
+        ),
+        (None, None) => Ok(()),
+    };
+    if let Err(error) = written {
+        bevy::log::warn!("failed to write save: {error}");
+    }
+}
+
+/// Reads a string value from the object store. Returns `false` when the read
+/// couldn't be issued; otherwise exactly one of the callbacks runs later.
+pub(crate) fn get(
+    key: &str,
+    on_value: impl FnOnce(Option<String>) + 'static,
+    on_error: impl FnOnce() + 'static,
+) -> bool {
+    let request = DATABASE.with(|slot| {
+        let database = slot.borrow();
+        let transaction = database.as_ref()?.transaction_with_str(STORE_NAME).ok()?;
+        transaction
+            .object_store(STORE_NAME)
+            .ok()?
+            .get(&JsValue::from_str(key))
+            .ok()
+    });
+    let Some(request) = request else {
+        return false;
+    };
+
+    let result_request: IdbRequest = request.clone();
+    request.set_onsuccess(Some(
+        Closure::once_into_js(move || {
+            on_value(
+                result_request
+                    .result()
+                    .ok()
+                    .and_then(|value| value.as_string()),
+            )
+        })
+        .unchecked_ref(),
+    ));
+    request.set_onerror(Some(Closure::once_into_js(on_error).unchecked_ref()));
+    true
+}
+
+/// Writes a string value in its own transaction; `on_complete` runs once the
+/// transaction commits, or `on_error` if it fails after being queued.
+pub(crate) fn put(
+    key: &str,
+    value: &str,
+    on_complete: impl FnOnce() + 'static,
+    on_error: impl FnOnce() + 'static,
+) -> Result<(), SaveError> {
+    let failed = |error: JsValue| SaveError::Browser(format!("IndexedDB write failed: {error:?}"));
+    DATABASE.with(|slot| {
+        let database = slot.borrow();
+        let database = database
+            .as_ref()
+            .ok_or_else(|| SaveError::Browser("IndexedDB is not open".into()))?;
+        let transaction = database
+            .transaction_with_str_and_mode(STORE_NAME, IdbTransactionMode::Readwrite)
+            .map_err(failed)?;
+        transaction
+            .object_store(STORE_NAME)
+            .and_then(|store| {
+                store.put_with_key(&JsValue::from_str(value), &JsValue::from_str(key))
+            })
+            .map_err(failed)?;
+        transaction.set_oncomplete(Some(Closure::once_into_js(on_complete).unchecked_ref()));
+        // A failed request aborts its transaction, as does running out of
+        // quota at commit, which raises no error event.
+        transaction.set_onabort(Some(Closure::once_into_js(on_error).unchecked_ref()));
+        Ok(())
+    })
+}
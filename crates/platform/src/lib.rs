@@ -1,3 +1,21 @@
+pub mod args;
+#[cfg(target_arch = "wasm32")]
+mod indexed_db;
+pub mod replay;
+pub mod save;
 pub mod storage;
 
-pub use storage::{load_currency, load_upgrades, save_currency, save_upgrades};
+pub use args::{launch_args, query_args};
+pub use replay::{ReplayLoader, LATEST_REPLAY};
+pub use save::{SaveData, SAVE_SCHEMA_VERSION};
+pub use storage::{
+    MemoryBackend, ReplayCallback, SaveBackend, SaveError, SaveStore, StandInBackend,
+};
+
+#[cfg(target_arch = "wasm32")]
+pub use indexed_db::IndexedDbBackend;
+#[cfg(target_arch = "wasm32")]
+pub use storage::LocalStorageBackend;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use storage::NativeFileBackend;
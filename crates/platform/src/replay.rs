@@ -0,0 +1,89 @@
+use crate::storage::SaveStore;
+use bevy::prelude::*;
+use std::sync::{Arc, Mutex};
+use threadweaver_core::replay::Replay;
+
+/// Name every finished run is stored under, replacing the one before.
+pub const LATEST_REPLAY: &str = "latest";
+
+pub(crate) fn encode(replay: &Replay) -> String {
+    serde_json::to_string(replay).unwrap_or_default()
+}
+
+pub(crate) fn decode(json: &str) -> Option<Replay> {
+    serde_json::from_str(json).ok()
+}
+
+/// Browser storage key for the replay called `name`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn storage_key(name: &str) -> String {
+    format!("threadweaver_replay_{name}")
+}
+
+/// Where the replay called `name` lives next to the save at `save_path`.
+/// Anything that already looks like a path is used as is.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn file_path(save_path: &std::path::Path, name: &str) -> std::path::PathBuf {
+    use std::path::Path;
+
+    let path = Path::new(name);
+    if path.extension().is_some() || path.components().count() > 1 {
+        return path.to_path_buf();
+    }
+    save_path
+        .parent()
+        .unwrap_or(Path::new("."))
+        .join("replays")
+        .join(format!("{name}.json"))
+}
+
+/// Reads a stored replay back for playback through the `SaveStore`, so it
+/// comes from wherever that store keeps them. Poll it once the store has
+/// loaded, so the web build knows which storage the save settled in.
+#[derive(Resource)]
+pub struct ReplayLoader {
+    name: String,
+    requested: bool,
+    result: Arc<Mutex<Option<Option<Replay>>>>,
+}
+
+impl ReplayLoader {
+    /// `name` is a stored replay's name or, on native builds, a file path.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            requested: false,
+            result: Arc::default(),
+        }
+    }
+
+    /// `--replay [name]` plays a stored replay, the last finished run when no
+    /// name is given. `None` when the args don't ask for one.
+    pub fn from_args(args: &[String]) -> Option<Self> {
+        let at = args.iter().position(|arg| arg == "--replay")?;
+        let name = args
+            .get(at + 1)
+            .filter(|name| !name.starts_with("--"))
+            .map_or(LATEST_REPLAY, String::as_str);
+        Some(Self::new(name))
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// `None` while the read is in flight; then the replay, or `Some(None)`
+    /// when there's no readable replay by that name.
+    pub fn poll(&mut self, store: &SaveStore) -> Option<Option<Replay>> {
+        if !self.requested {
+            self.requested = true;
+            let result = self.result.clone();
+            store.load_replay(&self.name, move |replay| {
+                if let Ok(mut result) = result.lock() {
+                    *result = Some(replay);
+                }
+            });
+        }
+        self.result.lock().ok()?.take()
+    }
+}
@@ -0,0 +1,160 @@
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use threadweaver_core::resources::{PurchasedUpgrades, RunRecord};
+
+/// Version written by this build. Bump it and append to `MIGRATIONS` whenever
+/// the layout of `SaveData` changes in a way old saves can't deserialize into.
+pub const SAVE_SCHEMA_VERSION: u32 = 2;
+
+/// localStorage keys used by web builds before the save was versioned.
+pub const LEGACY_CURRENCY_KEY: &str = "threadweaver_currency";
+pub const LEGACY_UPGRADES_KEY: &str = "threadweaver_upgrades";
+pub const LEGACY_BEST_SCORE_KEY: &str = "threadweaver_best_score";
+pub const LEGACY_RUN_HISTORY_KEY: &str = "threadweaver_run_history";
+
+pub const LEGACY_KEYS: [&str; 4] = [
+    LEGACY_CURRENCY_KEY,
+    LEGACY_UPGRADES_KEY,
+    LEGACY_BEST_SCORE_KEY,
+    LEGACY_RUN_HISTORY_KEY,
+];
+
+/// Everything the game persists, as one document.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SaveData {
+    pub schema_version: u32,
+    pub currency: u32,
+    pub upgrades: PurchasedUpgrades,
+    pub best_score: u32,
+    pub run_history: Vec<RunRecord>,
+}
+
+impl Default for SaveData {
+    fn default() -> Self {
+        Self {
+            schema_version: SAVE_SCHEMA_VERSION,
+            currency: 0,
+            upgrades: PurchasedUpgrades::default(),
+            best_score: 0,
+            run_history: Vec::new(),
+        }
+    }
+}
+
+impl SaveData {
+    /// Parses a save from any known layout and brings it up to date.
+    pub fn from_json(json: &str) -> Option<Self> {
+        serde_json::from_str(json).ok().map(migrate)
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+type Migration = fn(Map<String, Value>) -> Map<String, Value>;
+
+/// `MIGRATIONS[n]` upgrades a version `n` document to version `n + 1`.
+const MIGRATIONS: [Migration; SAVE_SCHEMA_VERSION as usize] =
+    [migrate_web_keys_to_native, migrate_native_to_versioned];
+
+/// Upgrades a save document of any version and reads it into `SaveData`.
+///
+/// Fields are read one at a time, so a single corrupt or retyped field falls
+/// back to its default instead of discarding the whole save; likewise a
+/// corrupt run record drops out of the history on its own. A save from a
+/// newer build is read as far as this build understands it but keeps its
+/// version, which stops `SaveStore` writing the fields it dropped over it.
+pub fn migrate(value: Value) -> SaveData {
+    let Value::Object(mut document) = value else {
+        return SaveData::default();
+    };
+
+    let mut version = detect_version(&document);
+    while version < SAVE_SCHEMA_VERSION {
+        document = MIGRATIONS[version as usize](document);
+        version += 1;
+    }
+
+    SaveData {
+        schema_version: version,
+        currency: field(&document, "currency"),
+        upgrades: field(&document, "upgrades"),
+        best_score: field(&document, "best_score"),
+        run_history: list_field(&document, "run_history"),
+    }
+}
+
+fn detect_version(document: &Map<String, Value>) -> u32 {
+    if let Some(version) = document.get("schema_version").and_then(Value::as_u64) {
+        // Too big to be any version this build knows, so newer than all of
+        // them, not an old layout to migrate.
+        return u32::try_from(version).unwrap_or(u32::MAX);
+    }
+    if LEGACY_KEYS.iter().any(|key| document.contains_key(*key)) {
+        0
+    } else {
+        1
+    }
+}
+
+fn field<T: DeserializeOwned + Default>(document: &Map<String, Value>, key: &str) -> T {
+    document
+        .get(key)
+        .cloned()
+        .and_then(|value| serde_json::from_value(value).ok())
+        .unwrap_or_default()
+}
+
+/// Reads a list field element by element, skipping the elements that don't
+/// deserialize.
+fn list_field<T: DeserializeOwned>(document: &Map<String, Value>, key: &str) -> Vec<T> {
+    let Some(Value::Array(items)) = document.get(key) else {
+        return Vec::new();
+    };
+    items
+        .iter()
+        .filter_map(|item| serde_json::from_value(item.clone()).ok())
+        .collect()
+}
+
+/// v0 -> v1: the web build kept each value under its own localStorage key as a
+/// raw string (JSON for the structured ones). Lift them into the object layout
+/// the native build already used.
+fn migrate_web_keys_to_native(document: Map<String, Value>) -> Map<String, Value> {
+    let number = |key: &str| {
+        document
+            .get(key)
+            .and_then(Value::as_str)
+            .and_then(|raw| raw.trim().parse::<u32>().ok())
+            .map(Value::from)
+    };
+    let json = |key: &str| {
+        document
+            .get(key)
+            .and_then(Value::as_str)
+            .and_then(|raw| serde_json::from_str::<Value>(raw).ok())
+    };
+
+    let mut migrated = Map::new();
+    let entries = [
+        ("currency", number(LEGACY_CURRENCY_KEY)),
+        ("upgrades", json(LEGACY_UPGRADES_KEY)),
+        ("best_score", number(LEGACY_BEST_SCORE_KEY)),
+        ("run_history", json(LEGACY_RUN_HISTORY_KEY)),
+    ];
+    for (key, value) in entries {
+        if let Some(value) = value {
+            migrated.insert(key.into(), value);
+        }
+    }
+    migrated
+}
+
+/// v1 -> v2: the unversioned native `NativeState` layout. Field names carry
+/// over unchanged; the document just gains the version stamp `migrate` puts
+/// on every save it reads.
+fn migrate_native_to_versioned(document: Map<String, Value>) -> Map<String, Value> {
+    document
+}
@@ -1,101 +1,539 @@
-use threadweaver_core::resources::PurchasedUpgrades;
+use crate::save::{SaveData, SAVE_SCHEMA_VERSION};
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use threadweaver_core::constants::SAVE_FLUSH_INTERVAL;
+use threadweaver_core::replay::Replay;
+
+/// Why a save couldn't be written.
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    /// The browser refused the write or has no storage to offer.
+    Browser(String),
+    /// The stored save is from a newer build; writing it would lose whatever
+    /// that build added.
+    NewerVersion(u32),
+    /// The stored save couldn't be read, so there's nothing safe to write
+    /// over it.
+    Unreadable,
+}
+
+impl fmt::Display for SaveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SaveError::Io(error) => write!(f, "{error}"),
+            SaveError::Browser(message) => f.write_str(message),
+            SaveError::NewerVersion(version) => write!(
+                f,
+                "save is schema version {version}, newer than this build's {SAVE_SCHEMA_VERSION}; leaving it untouched"
+            ),
+            SaveError::Unreadable => {
+                f.write_str("the stored save couldn't be read; leaving it untouched")
+            }
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+impl From<std::io::Error> for SaveError {
+    fn from(error: std::io::Error) -> Self {
+        SaveError::Io(error)
+    }
+}
+
+/// Receives a replay read by `SaveBackend::load_replay`.
+pub type ReplayCallback = Box<dyn FnOnce(Option<Replay>) + Send>;
+
+/// Where a `SaveData` document lives between sessions.
+pub trait SaveBackend: Send + Sync + 'static {
+    /// Reads the stored save, migrating older layouts. `None` when nothing has
+    /// been saved yet or the stored data is unreadable.
+    fn load(&self) -> Option<SaveData>;
+
+    fn store(&self, save: &SaveData) -> Result<(), SaveError>;
+
+    /// Keeps a recorded run under `name`, replacing any replay already
+    /// there. Backends with nowhere to put replays drop them.
+    fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+        let _ = (name, replay);
+        Ok(())
+    }
+
+    /// Reads back the replay stored under `name`, passing it to `on_loaded`
+    /// once the read finishes, or `None` when there's no readable replay by
+    /// that name.
+    fn load_replay(&self, name: &str, on_loaded: ReplayCallback) {
+        let _ = name;
+        on_loaded(None);
+    }
+
+    /// A write that failed after `store` had already returned `Ok`, reported
+    /// once. The `SaveStore` writes its save again on the next flush.
+    fn take_failed_write(&self) -> Option<SaveError> {
+        None
+    }
+
+    /// Whether `load` can be answered yet. Asynchronous backends return
+    /// `false` until their first read completes.
+    fn is_ready(&self) -> bool {
+        true
+    }
+}
+
+/// The active save backend plus the latest version of the save document.
+///
+/// Changes collect in memory and are written at most once per
+/// `SAVE_FLUSH_INTERVAL`, when `flush` is called, or when the store is dropped.
+///
+/// Insert one before `GameplayPlugin` to swap the backend, e.g. a
+/// `MemoryBackend` in headless tests; otherwise the platform default is used.
+#[derive(Resource)]
+pub struct SaveStore {
+    backend: Box<dyn SaveBackend>,
+    data: SaveData,
+    loaded: bool,
+    dirty: bool,
+    since_flush: f32,
+    last_error: Option<SaveError>,
+}
+
+impl SaveStore {
+    pub fn new(backend: impl SaveBackend) -> Self {
+        let mut store = Self {
+            backend: Box::new(backend),
+            data: SaveData::default(),
+            loaded: false,
+            dirty: false,
+            since_flush: 0.0,
+            last_error: None,
+        };
+        store.poll_loaded();
+        store
+    }
+
+    /// Prefers IndexedDB, falling back to `localStorage` when the browser
+    /// doesn't offer it.
+    #[cfg(target_arch = "wasm32")]
+    pub fn platform_default() -> Self {
+        match crate::indexed_db::IndexedDbBackend::open() {
+            Some(backend) => Self::new(backend),
+            None => Self::new(web_storage::LocalStorageBackend),
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn platform_default() -> Self {
+        Self::new(native_storage::NativeFileBackend::from_env())
+    }
+
+    pub fn data(&self) -> &SaveData {
+        &self.data
+    }
+
+    /// Picks up the stored save once the backend has finished reading it.
+    /// Returns whether the load is complete.
+    pub fn poll_loaded(&mut self) -> bool {
+        if !self.loaded && self.backend.is_ready() {
+            self.data = self.backend.load().unwrap_or_default();
+            self.loaded = true;
+        }
+        self.loaded
+    }
+
+    /// Applies `apply` to the cached save and marks it for the next flush.
+    pub fn update(&mut self, apply: impl FnOnce(&mut SaveData)) {
+        apply(&mut self.data);
+        self.dirty = true;
+    }
+
+    /// Advances the flush timer, writing pending changes once it elapses.
+    pub fn tick(&mut self, delta: f32) {
+        self.since_flush += delta;
+        if self.since_flush >= SAVE_FLUSH_INTERVAL {
+            self.flush();
+        }
+    }
+
+    /// Writes pending changes now. A failed write stays pending and is retried
+    /// on the next flush.
+    ///
+    /// Writes are held back until the load completes so a default document
+    /// never overwrites the player's real save.
+    pub fn flush(&mut self) {
+        self.since_flush = 0.0;
+        if let Some(error) = self.backend.take_failed_write() {
+            self.write_failed(error);
+        }
+        if !self.loaded || !self.dirty {
+            return;
+        }
+        let written = if self.data.schema_version > SAVE_SCHEMA_VERSION {
+            Err(SaveError::NewerVersion(self.data.schema_version))
+        } else {
+            self.backend.store(&self.data)
+        };
+        match written {
+            Ok(()) => {
+                self.dirty = false;
+                self.last_error = None;
+            }
+            Err(error) => self.write_failed(error),
+        }
+    }
+
+    fn write_failed(&mut self, error: SaveError) {
+        // Only report the first failure of a streak; retries would otherwise
+        // log every interval.
+        if self.last_error.is_none() {
+            warn!("failed to write save: {error}");
+        }
+        self.last_error = Some(error);
+        self.dirty = true;
+    }
+
+    /// Writes a recorded run straight away rather than on the next flush.
+    pub fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+        self.backend.store_replay(name, replay)
+    }
+
+    /// Reads a replay back from wherever this store keeps them.
+    pub fn load_replay(&self, name: &str, on_loaded: impl FnOnce(Option<Replay>) + Send + 'static) {
+        self.backend.load_replay(name, Box::new(on_loaded));
+    }
+
+    /// The error from the most recent write, if it failed.
+    pub fn last_error(&self) -> Option<&SaveError> {
+        self.last_error.as_ref()
+    }
+}
+
+impl Drop for SaveStore {
+    fn drop(&mut self) {
+        self.flush();
+    }
+}
+
+/// Keeps the save in memory only. Clones share the same slot, so a test can
+/// hold on to one handle after giving another to `SaveStore`.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    slot: Arc<Mutex<Option<SaveData>>>,
+    replays: Arc<Mutex<HashMap<String, Replay>>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_save(save: SaveData) -> Self {
+        Self {
+            slot: Arc::new(Mutex::new(Some(save))),
+            ..Self::default()
+        }
+    }
+
+    pub fn snapshot(&self) -> Option<SaveData> {
+        self.slot.lock().ok()?.clone()
+    }
+
+    pub fn replay(&self, name: &str) -> Option<Replay> {
+        self.replays.lock().ok()?.get(name).cloned()
+    }
+}
+
+impl SaveBackend for MemoryBackend {
+    fn load(&self) -> Option<SaveData> {
+        self.snapshot()
+    }
+
+    fn store(&self, save: &SaveData) -> Result<(), SaveError> {
+        if let Ok(mut slot) = self.slot.lock() {
+            *slot = Some(save.clone());
+        }
+        Ok(())
+    }
+
+    fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+        if let Ok(mut replays) = self.replays.lock() {
+            replays.insert(name.to_owned(), replay.clone());
+        }
+        Ok(())
+    }
+
+    fn load_replay(&self, name: &str, on_loaded: ReplayCallback) {
+        on_loaded(self.replay(name));
+    }
+}
+
+/// Takes over from a backend whose save is there but couldn't be read, e.g.
+/// IndexedDB failing mid-read. Everything goes to `fallback`, except that a
+/// fallback with no save of its own refuses writes: the defaults this session
+/// starts from must not become the save the next session finds.
+pub struct StandInBackend<B> {
+    fallback: B,
+    writable: bool,
+}
+
+impl<B: SaveBackend> StandInBackend<B> {
+    pub fn new(fallback: B) -> Self {
+        let writable = fallback.load().is_some();
+        Self { fallback, writable }
+    }
+}
+
+impl<B: SaveBackend> SaveBackend for StandInBackend<B> {
+    fn load(&self) -> Option<SaveData> {
+        self.fallback.load()
+    }
+
+    fn store(&self, save: &SaveData) -> Result<(), SaveError> {
+        if !self.writable {
+            return Err(SaveError::Unreadable);
+        }
+        self.fallback.store(save)
+    }
+
+    fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+        self.fallback.store_replay(name, replay)
+    }
+
+    fn load_replay(&self, name: &str, on_loaded: ReplayCallback) {
+        self.fallback.load_replay(name, on_loaded);
+    }
+}
 
 #[cfg(target_arch = "wasm32")]
 mod web_storage {
-    use super::*;
-    use wasm_bindgen::JsCast;
+    use super::{ReplayCallback, SaveBackend, SaveError};
+    use crate::replay;
+    use crate::save::{SaveData, LEGACY_KEYS};
+    use serde_json::{Map, Value};
+    use threadweaver_core::replay::Replay;
     use web_sys::Storage;
 
-    const CURRENCY_KEY: &str = "threadweaver_currency";
-    const UPGRADES_KEY: &str = "threadweaver_upgrades";
+    const SAVE_KEY: &str = "threadweaver_save";
+
+    /// Persists the save as one JSON string in `window.localStorage`.
+    pub struct LocalStorageBackend;
 
     fn local_storage() -> Option<Storage> {
         web_sys::window()?.local_storage().ok().flatten()
     }
 
-    pub fn load_currency() -> u32 {
-        local_storage()
-            .and_then(|storage| storage.get_item(CURRENCY_KEY).ok().flatten())
-            .and_then(|value| value.parse::<u32>().ok())
-            .unwrap_or_default()
-    }
-
-    pub fn save_currency(value: u32) {
-        if let Some(storage) = local_storage() {
-            let _ = storage.set_item(CURRENCY_KEY, &value.to_string());
+    impl LocalStorageBackend {
+        /// Removes every key this backend (or an older build) wrote, once the
+        /// save has moved somewhere else.
+        pub(crate) fn clear(&self) {
+            let Some(storage) = local_storage() else {
+                return;
+            };
+            let _ = storage.remove_item(SAVE_KEY);
+            for key in LEGACY_KEYS {
+                let _ = storage.remove_item(key);
+            }
         }
     }
 
-    pub fn load_upgrades() -> PurchasedUpgrades {
-        local_storage()
-            .and_then(|storage| storage.get_item(UPGRADES_KEY).ok().flatten())
-            .and_then(|json| serde_json::from_str(&json).ok())
-            .unwrap_or_default()
-    }
+    impl SaveBackend for LocalStorageBackend {
+        fn load(&self) -> Option<SaveData> {
+            let storage = local_storage()?;
+            if let Some(json) = storage.get_item(SAVE_KEY).ok().flatten() {
+                return SaveData::from_json(&json);
+            }
 
-    pub fn save_upgrades(upgrades: &PurchasedUpgrades) {
-        if let Some(storage) = local_storage() {
-            if let Ok(json) = serde_json::to_string(upgrades) {
-                let _ = storage.set_item(UPGRADES_KEY, &json);
+            // Pre-versioned builds kept one key per value; gather whatever is
+            // there into a single document for the migration chain.
+            let mut legacy = Map::new();
+            for key in LEGACY_KEYS {
+                if let Some(value) = storage.get_item(key).ok().flatten() {
+                    legacy.insert(key.into(), Value::String(value));
+                }
+            }
+            if legacy.is_empty() {
+                return None;
             }
+            Some(crate::save::migrate(Value::Object(legacy)))
+        }
+
+        fn store(&self, save: &SaveData) -> Result<(), SaveError> {
+            let storage = local_storage()
+                .ok_or_else(|| SaveError::Browser("localStorage is unavailable".into()))?;
+            storage
+                .set_item(SAVE_KEY, &save.to_json())
+                .map_err(|error| {
+                    SaveError::Browser(format!("localStorage write failed: {error:?}"))
+                })?;
+            for key in LEGACY_KEYS {
+                let _ = storage.remove_item(key);
+            }
+            Ok(())
+        }
+
+        fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+            local_storage()
+                .ok_or_else(|| SaveError::Browser("localStorage is unavailable".into()))?
+                .set_item(&replay::storage_key(name), &replay::encode(replay))
+                .map_err(|error| {
+                    SaveError::Browser(format!("localStorage write failed: {error:?}"))
+                })
+        }
+
+        fn load_replay(&self, name: &str, on_loaded: ReplayCallback) {
+            let json = local_storage()
+                .and_then(|storage| storage.get_item(&replay::storage_key(name)).ok().flatten());
+            on_loaded(json.and_then(|json| replay::decode(&json)));
         }
     }
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 mod native_storage {
-    use super::*;
+    use super::{ReplayCallback, SaveBackend, SaveError};
+    use crate::replay;
+    use crate::save::SaveData;
     use std::fs;
-    use std::path::PathBuf;
+    use std::io::Write;
+    use std::path::{Path, PathBuf};
+    use threadweaver_core::replay::Replay;
 
+    const APP_DIR: &str = "threadweaver";
     const FILE_NAME: &str = "threadweaver_state.json";
 
-    fn storage_path() -> PathBuf {
-        std::env::var_os("THREADWEAVER_DATA")
-            .map(PathBuf::from)
-            .unwrap_or_else(|| std::env::temp_dir().join(FILE_NAME))
+    /// Persists the save as a JSON file on disk.
+    pub struct NativeFileBackend {
+        pub path: PathBuf,
     }
 
-    pub fn load_currency() -> u32 {
-        load_state().map(|state| state.currency).unwrap_or_default()
+    impl NativeFileBackend {
+        /// Uses `THREADWEAVER_DATA` when set, else the platform's user data
+        /// directory, else the temp dir.
+        pub fn from_env() -> Self {
+            let path = std::env::var_os("THREADWEAVER_DATA")
+                .map(PathBuf::from)
+                .or_else(|| user_data_dir().map(|dir| dir.join(APP_DIR).join(FILE_NAME)))
+                .unwrap_or_else(|| std::env::temp_dir().join(FILE_NAME));
+            Self { path }
+        }
     }
 
-    pub fn save_currency(value: u32) {
-        let mut state = load_state().unwrap_or_default();
-        state.currency = value;
-        let _ = save_state(state);
+    /// `%APPDATA%` on Windows, `~/Library/Application Support` on macOS and
+    /// `$XDG_DATA_HOME` (or `~/.local/share`) elsewhere.
+    fn user_data_dir() -> Option<PathBuf> {
+        let var = |name: &str| {
+            std::env::var_os(name)
+                .filter(|value| !value.is_empty())
+                .map(PathBuf::from)
+        };
+        if cfg!(target_os = "windows") {
+            var("APPDATA")
+        } else if cfg!(target_os = "macos") {
+            var("HOME").map(|home| home.join("Library/Application Support"))
+        } else {
+            var("XDG_DATA_HOME").or_else(|| var("HOME").map(|home| home.join(".local/share")))
+        }
     }
 
-    pub fn load_upgrades() -> PurchasedUpgrades {
-        load_state().map(|state| state.upgrades).unwrap_or_default()
-    }
+    /// Writes next to the target and renames over it, so a crash mid-write
+    /// leaves the previous save intact instead of a truncated file.
+    fn write_atomic(path: &Path, contents: &str) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut temp_name = path.as_os_str().to_owned();
+        temp_name.push(".tmp");
+        let temp_path = PathBuf::from(temp_name);
 
-    pub fn save_upgrades(upgrades: &PurchasedUpgrades) {
-        let mut state = load_state().unwrap_or_default();
-        state.upgrades = upgrades.clone();
-        let _ = save_state(state);
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents.as_bytes())?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, path)
     }
 
-    #[derive(Default, serde::Serialize, serde::Deserialize)]
-    struct NativeState {
-        currency: u32,
-        upgrades: PurchasedUpgrades,
-    }
+    impl SaveBackend for NativeFileBackend {
+        fn load(&self) -> Option<SaveData> {
+            let data = fs::read_to_string(&self.path).ok()?;
+            SaveData::from_json(&data)
+        }
+
+        fn store(&self, save: &SaveData) -> Result<(), SaveError> {
+            write_atomic(&self.path, &save.to_json())?;
+            Ok(())
+        }
+
+        /// Replays go in a `replays` folder beside the save file.
+        fn store_replay(&self, name: &str, replay: &Replay) -> Result<(), SaveError> {
+            write_atomic(
+                &replay::file_path(&self.path, name),
+                &replay::encode(replay),
+            )?;
+            Ok(())
+        }
 
-    fn load_state() -> Option<NativeState> {
-        let path = storage_path();
-        let data = fs::read_to_string(path).ok()?;
-        serde_json::from_str(&data).ok()
+        /// `name` may also be a path to a replay file.
+        fn load_replay(&self, name: &str, on_loaded: ReplayCallback) {
+            let replay = fs::read_to_string(replay::file_path(&self.path, name))
+                .ok()
+                .and_then(|json| replay::decode(&json));
+            on_loaded(replay);
+        }
     }
 
-    fn save_state(state: NativeState) -> std::io::Result<()> {
-        let data = serde_json::to_string(&state).unwrap();
-        fs::write(storage_path(), data)
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        /// A fresh directory under the system temp dir for one test.
+        fn scratch(name: &str) -> PathBuf {
+            let dir =
+                std::env::temp_dir().join(format!("threadweaver-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&dir);
+            dir
+        }
+
+        #[test]
+        fn write_atomic_creates_folders_and_replaces_the_file() {
+            let dir = scratch("write-atomic");
+            let path = dir.join("nested").join("save.json");
+
+            write_atomic(&path, "first").unwrap();
+            write_atomic(&path, "second").unwrap();
+
+            assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+            let leftovers: Vec<_> = fs::read_dir(path.parent().unwrap())
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name())
+                .collect();
+            assert_eq!(leftovers, ["save.json"]);
+            fs::remove_dir_all(dir).unwrap();
+        }
+
+        #[test]
+        fn file_backend_round_trips_a_save() {
+            let dir = scratch("file-backend");
+            let backend = NativeFileBackend {
+                path: dir.join("threadweaver_state.json"),
+            };
+            assert_eq!(backend.load(), None);
+
+            let save = SaveData {
+                currency: 31,
+                ..SaveData::default()
+            };
+            backend.store(&save).unwrap();
+
+            assert_eq!(backend.load(), Some(save));
+            fs::remove_dir_all(dir).unwrap();
+        }
     }
 }
 
 #[cfg(target_arch = "wasm32")]
-pub use web_storage::{load_currency, load_upgrades, save_currency, save_upgrades};
+pub use web_storage::LocalStorageBackend;
 
 #[cfg(not(target_arch = "wasm32"))]
-pub use native_storage::{load_currency, load_upgrades, save_currency, save_upgrades};
+pub use native_storage::NativeFileBackend;
@@ -0,0 +1,138 @@
+use threadweaver_core::resources::PurchasedUpgrades;
+use threadweaver_platform::{MemoryBackend, SaveData, SaveError, SaveStore, SAVE_SCHEMA_VERSION};
+
+fn load(fixture: &str) -> SaveData {
+    SaveData::from_json(fixture).expect("fixture should parse as JSON")
+}
+
+fn upgrades(movement: u32, health: u32, trail: u32, shield: u32) -> PurchasedUpgrades {
+    PurchasedUpgrades {
+        movement_speed_level: movement,
+        max_health_level: health,
+        trail_damage_level: trail,
+        shield_level: shield,
+    }
+}
+
+#[test]
+fn migrates_v0_web_keys() {
+    let save = load(include_str!("fixtures/v0_web_keys.json"));
+
+    assert_eq!(save.schema_version, SAVE_SCHEMA_VERSION);
+    assert_eq!(save.currency, 42);
+    assert_eq!(save.upgrades, upgrades(2, 1, 0, 3));
+    assert_eq!(save.best_score, 0);
+    assert!(save.run_history.is_empty());
+}
+
+#[test]
+fn migrates_v0_web_keys_with_history() {
+    let save = load(include_str!("fixtures/v0_web_keys_with_history.json"));
+
+    assert_eq!(save.currency, 7);
+    assert_eq!(save.upgrades, upgrades(1, 0, 2, 0));
+    assert_eq!(save.best_score, 615);
+    assert_eq!(save.run_history.len(), 1);
+    assert_eq!(save.run_history[0].kills(), 41);
+    assert_eq!(save.run_history[0].duration(), 48.5);
+}
+
+#[test]
+fn migrates_v1_native() {
+    let save = load(include_str!("fixtures/v1_native.json"));
+
+    assert_eq!(save.schema_version, SAVE_SCHEMA_VERSION);
+    assert_eq!(save.currency, 18);
+    assert_eq!(save.upgrades, upgrades(0, 2, 1, 0));
+    assert_eq!(save.best_score, 0);
+    assert!(save.run_history.is_empty());
+}
+
+#[test]
+fn migrates_v1_native_with_history() {
+    let save = load(include_str!("fixtures/v1_native_with_history.json"));
+
+    assert_eq!(save.currency, 3);
+    assert_eq!(save.upgrades, upgrades(4, 4, 3, 3));
+    assert_eq!(save.best_score, 1280);
+    assert_eq!(save.run_history.len(), 2);
+    assert_eq!(save.run_history[1].score, 1280);
+    assert_eq!(save.run_history[1].stats.peak_combo, 22);
+}
+
+#[test]
+fn loads_v2_unchanged() {
+    let json = include_str!("fixtures/v2_versioned.json");
+    let save = load(json);
+
+    assert_eq!(save.currency, 55);
+    assert_eq!(save.upgrades, upgrades(1, 1, 1, 1));
+    assert_eq!(save.run_history[0].seed, Some(12345));
+
+    let round_trip = load(&save.to_json());
+    assert_eq!(round_trip, save);
+}
+
+#[test]
+fn corrupt_fields_fall_back_individually() {
+    let save = load(include_str!("fixtures/v2_partially_corrupt.json"));
+
+    assert_eq!(save.currency, 0);
+    assert_eq!(save.upgrades, upgrades(2, 0, 0, 1));
+    assert_eq!(save.best_score, 410);
+    assert!(save.run_history.is_empty());
+}
+
+#[test]
+fn corrupt_run_records_are_skipped_individually() {
+    let save = load(include_str!("fixtures/v2_corrupt_run_record.json"));
+
+    let scores: Vec<u32> = save.run_history.iter().map(|record| record.score).collect();
+    assert_eq!(scores, [300, 900]);
+    assert_eq!(save.run_history[1].stats.peak_combo, 14);
+    assert_eq!(save.best_score, 900);
+}
+
+#[test]
+fn rejects_non_json() {
+    assert!(SaveData::from_json("not json").is_none());
+    assert_eq!(
+        threadweaver_platform::save::migrate(serde_json::Value::Null),
+        SaveData::default()
+    );
+}
+
+#[test]
+fn newer_saves_keep_their_version() {
+    let json = r#"{"schema_version":9,"currency":12,"best_score":300,"future_field":true}"#;
+    let save = load(json);
+
+    assert_eq!(save.schema_version, 9);
+    assert_eq!(save.currency, 12);
+    assert_eq!(save.best_score, 300);
+}
+
+#[test]
+fn versions_past_u32_are_newer_not_old() {
+    let save = load(r#"{"schema_version":4294967297,"currency":12,"best_score":300}"#);
+
+    assert_eq!(save.schema_version, u32::MAX);
+    assert_eq!(save.currency, 12);
+    assert_eq!(save.best_score, 300);
+}
+
+#[test]
+fn newer_saves_are_never_written_back() {
+    let newer = load(r#"{"schema_version":9,"currency":12}"#);
+    let backend = MemoryBackend::with_save(newer.clone());
+    let mut store = SaveStore::new(backend.clone());
+
+    store.update(|save| save.currency += 5);
+    store.flush();
+
+    assert!(matches!(
+        store.last_error(),
+        Some(SaveError::NewerVersion(9))
+    ));
+    assert_eq!(backend.snapshot(), Some(newer));
+}
@@ -0,0 +1,257 @@
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use threadweaver_core::constants::SAVE_FLUSH_INTERVAL;
+use threadweaver_core::replay::Replay;
+use threadweaver_platform::{
+    MemoryBackend, ReplayLoader, SaveBackend, SaveData, SaveError, SaveStore, StandInBackend,
+    LATEST_REPLAY,
+};
+
+#[test]
+fn empty_backend_starts_from_defaults() {
+    let backend = MemoryBackend::new();
+    let mut store = SaveStore::new(backend.clone());
+
+    assert!(store.poll_loaded());
+    assert_eq!(store.data(), &SaveData::default());
+    assert_eq!(backend.snapshot(), None);
+}
+
+#[test]
+fn loads_the_stored_save() {
+    let save = SaveData {
+        currency: 40,
+        best_score: 900,
+        ..SaveData::default()
+    };
+    let store = SaveStore::new(MemoryBackend::with_save(save.clone()));
+
+    assert_eq!(store.data(), &save);
+}
+
+#[test]
+fn updates_reach_the_backend_on_flush() {
+    let backend = MemoryBackend::new();
+    let mut store = SaveStore::new(backend.clone());
+
+    store.update(|save| save.currency = 12);
+    assert_eq!(backend.snapshot(), None);
+
+    store.flush();
+    assert_eq!(backend.snapshot().map(|save| save.currency), Some(12));
+}
+
+#[test]
+fn updates_reach_the_backend_once_the_interval_passes() {
+    let backend = MemoryBackend::new();
+    let mut store = SaveStore::new(backend.clone());
+
+    store.update(|save| save.best_score = 300);
+    store.tick(SAVE_FLUSH_INTERVAL * 0.5);
+    assert_eq!(backend.snapshot(), None);
+
+    store.tick(SAVE_FLUSH_INTERVAL * 0.5);
+    assert_eq!(backend.snapshot().map(|save| save.best_score), Some(300));
+}
+
+#[test]
+fn a_new_store_reads_what_the_last_one_wrote() {
+    let backend = MemoryBackend::new();
+    {
+        let mut store = SaveStore::new(backend.clone());
+        store.update(|save| save.currency = 7);
+        store.flush();
+    }
+
+    let store = SaveStore::new(backend);
+    assert_eq!(store.data().currency, 7);
+}
+
+/// Counts writes and can be told to fail them, on top of `MemoryBackend`,
+/// either straight away or after reporting success as browser storage does.
+#[derive(Clone, Default)]
+struct FlakyBackend {
+    inner: MemoryBackend,
+    writes: Arc<AtomicUsize>,
+    failing: Arc<AtomicBool>,
+    failed_later: Arc<AtomicBool>,
+}
+
+impl FlakyBackend {
+    fn writes(&self) -> usize {
+        self.writes.load(Ordering::SeqCst)
+    }
+
+    fn set_failing(&self, failing: bool) {
+        self.failing.store(failing, Ordering::SeqCst);
+    }
+
+    fn fail_last_write(&self) {
+        self.failed_later.store(true, Ordering::SeqCst);
+    }
+}
+
+impl SaveBackend for FlakyBackend {
+    fn load(&self) -> Option<SaveData> {
+        self.inner.load()
+    }
+
+    fn store(&self, save: &SaveData) -> Result<(), SaveError> {
+        if self.failing.load(Ordering::SeqCst) {
+            return Err(SaveError::Io(std::io::Error::other("disk full")));
+        }
+        self.writes.fetch_add(1, Ordering::SeqCst);
+        self.inner.store(save)
+    }
+
+    fn take_failed_write(&self) -> Option<SaveError> {
+        self.failed_later
+            .swap(false, Ordering::SeqCst)
+            .then(|| SaveError::Browser("transaction aborted".into()))
+    }
+}
+
+#[test]
+fn changes_within_an_interval_coalesce_into_one_write() {
+    let backend = FlakyBackend::default();
+    let mut store = SaveStore::new(backend.clone());
+
+    for coins in 1..=5 {
+        store.update(|save| save.currency = coins);
+        store.tick(SAVE_FLUSH_INTERVAL / 10.0);
+    }
+    assert_eq!(backend.writes(), 0);
+
+    store.tick(SAVE_FLUSH_INTERVAL);
+    assert_eq!(backend.writes(), 1);
+    assert_eq!(backend.inner.snapshot().map(|save| save.currency), Some(5));
+}
+
+#[test]
+fn clean_stores_do_not_write() {
+    let backend = FlakyBackend::default();
+    let mut store = SaveStore::new(backend.clone());
+
+    store.tick(SAVE_FLUSH_INTERVAL * 3.0);
+    store.flush();
+    assert_eq!(backend.writes(), 0);
+
+    store.update(|save| save.currency = 1);
+    store.flush();
+    store.flush();
+    store.tick(SAVE_FLUSH_INTERVAL * 3.0);
+    assert_eq!(backend.writes(), 1);
+}
+
+#[test]
+fn failed_writes_are_retried() {
+    let backend = FlakyBackend::default();
+    backend.set_failing(true);
+    let mut store = SaveStore::new(backend.clone());
+
+    store.update(|save| save.best_score = 77);
+    store.flush();
+    assert!(matches!(store.last_error(), Some(SaveError::Io(_))));
+    assert_eq!(backend.inner.snapshot(), None);
+
+    store.tick(SAVE_FLUSH_INTERVAL);
+    assert!(store.last_error().is_some());
+
+    backend.set_failing(false);
+    store.tick(SAVE_FLUSH_INTERVAL);
+    assert!(store.last_error().is_none());
+    assert_eq!(
+        backend.inner.snapshot().map(|save| save.best_score),
+        Some(77)
+    );
+    assert_eq!(backend.writes(), 1);
+}
+
+#[test]
+fn writes_that_fail_after_returning_are_redone() {
+    let backend = FlakyBackend::default();
+    let mut store = SaveStore::new(backend.clone());
+
+    store.update(|save| save.currency = 21);
+    store.flush();
+    assert_eq!(backend.writes(), 1);
+
+    // Nothing changed since, but the save is written again all the same.
+    backend.fail_last_write();
+    store.tick(SAVE_FLUSH_INTERVAL);
+    assert_eq!(backend.writes(), 2);
+    assert!(store.last_error().is_none());
+
+    store.tick(SAVE_FLUSH_INTERVAL);
+    assert_eq!(backend.writes(), 2);
+}
+
+#[test]
+fn dropping_the_store_writes_pending_changes() {
+    let backend = FlakyBackend::default();
+    let mut store = SaveStore::new(backend.clone());
+    store.update(|save| save.currency = 9);
+    drop(store);
+
+    assert_eq!(backend.writes(), 1);
+    assert_eq!(backend.inner.snapshot().map(|save| save.currency), Some(9));
+}
+
+#[test]
+fn stand_ins_never_write_defaults_over_an_unread_save() {
+    // The real save went unread and the fallback has nothing of its own.
+    let fallback = MemoryBackend::new();
+    let mut store = SaveStore::new(StandInBackend::new(fallback.clone()));
+    assert_eq!(store.data(), &SaveData::default());
+
+    store.update(|save| save.currency = 5);
+    store.flush();
+    assert!(matches!(store.last_error(), Some(SaveError::Unreadable)));
+    assert_eq!(fallback.snapshot(), None);
+}
+
+#[test]
+fn stand_ins_keep_a_fallback_save_up_to_date() {
+    let fallback = MemoryBackend::with_save(SaveData {
+        currency: 40,
+        ..SaveData::default()
+    });
+    let mut store = SaveStore::new(StandInBackend::new(fallback.clone()));
+    assert_eq!(store.data().currency, 40);
+
+    store.update(|save| save.currency = 45);
+    store.flush();
+    assert!(store.last_error().is_none());
+    assert_eq!(fallback.snapshot().map(|save| save.currency), Some(45));
+}
+
+fn args(args: &[&str]) -> Vec<String> {
+    args.iter().map(|arg| arg.to_string()).collect()
+}
+
+#[test]
+fn replays_load_back_through_the_store() {
+    let store = SaveStore::new(MemoryBackend::new());
+    let mut replay = Replay::default();
+    replay.seed = 77;
+    replay.score = 1_250;
+    store.store_replay(LATEST_REPLAY, &replay).unwrap();
+    store.store_replay("other", &Replay::default()).unwrap();
+
+    let mut latest = ReplayLoader::from_args(&args(&["--replay"])).unwrap();
+    assert_eq!(latest.name(), LATEST_REPLAY);
+    assert_eq!(latest.poll(&store), Some(Some(replay)));
+
+    let mut named = ReplayLoader::from_args(&args(&["--replay", "other", "--daily"])).unwrap();
+    assert_eq!(named.poll(&store), Some(Some(Replay::default())));
+}
+
+#[test]
+fn missing_replays_load_as_none() {
+    let store = SaveStore::new(MemoryBackend::new());
+    let mut loader = ReplayLoader::from_args(&args(&["--replay", "--seed", "3"])).unwrap();
+
+    assert_eq!(loader.name(), LATEST_REPLAY);
+    assert_eq!(loader.poll(&store), Some(None));
+    assert!(ReplayLoader::from_args(&args(&["--daily"])).is_none());
+}
@@ -5,7 +5,8 @@ use bevy::window::{PrimaryWindow, WindowResized};
 use threadweaver_core::components::*;
 use threadweaver_core::prelude::*;
 use threadweaver_core::shop::{ShopItem, UpgradeType, SHOP_ITEMS};
-use threadweaver_gameplay::ShopPurchaseEvent;
+use threadweaver_core::state::{AppState, GameState};
+use threadweaver_gameplay::{ComboMilestoneEvent, ShopPurchaseEvent};
 
 pub struct ThreadweaverUiPlugin;
 
@@ -20,6 +21,8 @@ pub struct UiTheme {
     pub accent_soft: Color,
     pub text_primary: Color,
     pub text_muted: Color,
+    pub combo_warm: Color,
+    pub combo_hot: Color,
 }
 
 impl Default for UiTheme {
@@ -31,6 +34,8 @@ impl Default for UiTheme {
             accent_soft: Color::srgba(0.33, 0.66, 0.93, 0.25),
             text_primary: Color::srgb(0.92, 0.95, 0.98),
             text_muted: Color::srgb(0.66, 0.72, 0.82),
+            combo_warm: Color::srgb(0.98, 0.78, 0.32),
+            combo_hot: Color::srgb(0.96, 0.38, 0.56),
         }
     }
 }
@@ -67,11 +72,21 @@ impl Default for UiLayout {
 #[derive(Component)]
 struct ShopGrid;
 
+const COMBO_FONT_SIZE: f32 = 18.0;
+const COMBO_PULSE_DURATION: f32 = 0.6;
+
 impl Plugin for ThreadweaverUiPlugin {
     fn build(&self, app: &mut App) {
         app.insert_resource(UiTheme::default())
             .insert_resource(UiLayout::default())
             .add_systems(PostStartup, (setup_ui, initialize_layout).chain())
+            .add_systems(OnEnter(AppState::Title), spawn_title_overlay)
+            .add_systems(OnEnter(AppState::InGame), show_shop_button)
+            .add_systems(OnExit(AppState::InGame), hide_shop_button)
+            .add_systems(OnEnter(AppState::GameOver), spawn_game_over_overlay)
+            .add_systems(OnEnter(GameState::Paused), spawn_pause_overlay)
+            .add_systems(OnEnter(GameState::Shop), open_shop_overlay)
+            .add_systems(OnExit(GameState::Shop), close_shop_overlay)
             .configure_sets(Update, UiSet)
             .add_systems(Update, update_layout_class.in_set(UiSet))
             .add_systems(
@@ -83,14 +98,17 @@ impl Plugin for ThreadweaverUiPlugin {
                     apply_layout_to_shop_grid,
                     apply_layout_to_shop_cards,
                     update_hud,
+                    pulse_combo_on_milestone,
                     update_health_bar,
-                    update_shop_button_label,
-                    sync_shop_visibility,
+                    update_boss_bar,
                     handle_shop_open_close,
                     handle_shop_purchases,
-                    handle_keyboard_navigation,
+                    handle_keyboard_navigation.run_if(in_state(GameState::Shop)),
+                    handle_pause_input,
                     handle_gamepad_navigation,
                     highlight_selected_card,
+                    handle_start_input
+                        .run_if(in_state(AppState::Title).or_else(in_state(AppState::GameOver))),
                 )
                     .in_set(UiSet),
             );
@@ -147,6 +165,18 @@ fn setup_ui(mut commands: Commands, theme: Res<UiTheme>, assets: Res<GameAssets>
                 HudStatus,
             ));
 
+            hud.spawn((
+                TextBundle::from_section(
+                    "Wave 1",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: theme.text_primary,
+                    },
+                ),
+                HudWave,
+            ));
+
             hud.spawn(NodeBundle {
                 style: Style {
                     width: Val::Percent(100.0),
@@ -198,6 +228,18 @@ fn setup_ui(mut commands: Commands, theme: Res<UiTheme>, assets: Res<GameAssets>
                 HudBuffs,
             ));
 
+            hud.spawn((
+                TextBundle::from_section(
+                    "No Combo",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: COMBO_FONT_SIZE,
+                        color: theme.text_muted,
+                    },
+                ),
+                HudCombo,
+            ));
+
             hud.spawn((
                 TextBundle::from_section(
                     "Currency 0",
@@ -207,10 +249,85 @@ fn setup_ui(mut commands: Commands, theme: Res<UiTheme>, assets: Res<GameAssets>
                         color: theme.text_primary,
                     },
                 ),
-                HudCombo,
+                HudCurrency,
+            ));
+
+            hud.spawn((
+                TextBundle::from_section(
+                    "Weapon Trail",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 18.0,
+                        color: theme.text_muted,
+                    },
+                ),
+                HudWeapon,
             ));
         });
 
+    // Boss health bar top center, shown while a boss is alive
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(16.0),
+                    left: Val::Percent(30.0),
+                    width: Val::Percent(40.0),
+                    padding: UiRect::axes(Val::Px(16.0), Val::Px(10.0)),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    row_gap: Val::Px(8.0),
+                    ..Default::default()
+                },
+                background_color: theme.panel_background.into(),
+                border_radius: BorderRadius::all(Val::Px(12.0)),
+                visibility: Visibility::Hidden,
+                ..Default::default()
+            },
+            HudBossBar,
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 20.0,
+                        color: theme.combo_hot,
+                    },
+                ),
+                HudBossName,
+            ));
+
+            panel
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(14.0),
+                        ..Default::default()
+                    },
+                    background_color: theme.accent_soft.into(),
+                    border_radius: BorderRadius::all(Val::Px(7.0)),
+                    ..Default::default()
+                })
+                .with_children(|bar| {
+                    bar.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.0),
+                                height: Val::Percent(100.0),
+                                ..Default::default()
+                            },
+                            background_color: theme.combo_hot.into(),
+                            border_radius: BorderRadius::all(Val::Px(7.0)),
+                            ..Default::default()
+                        },
+                        HudBossFill,
+                    ));
+                });
+        });
+
     // Shop button bottom center
     commands
         .spawn((
@@ -229,6 +346,7 @@ fn setup_ui(mut commands: Commands, theme: Res<UiTheme>, assets: Res<GameAssets>
                 background_color: theme.accent_soft.into(),
                 border_color: theme.accent.into(),
                 border_radius: BorderRadius::all(Val::Px(24.0)),
+                visibility: Visibility::Hidden,
                 ..Default::default()
             },
             ShopButton,
@@ -558,17 +676,25 @@ fn apply_layout_to_shop_cards(layout: Res<UiLayout>, mut cards: Query<&mut Style
     }
 }
 
+#[allow(clippy::too_many_arguments, clippy::type_complexity)]
 fn update_hud(
     score: Res<Score>,
+    combo: Res<ComboState>,
     currency: Res<Currency>,
+    theme: Res<UiTheme>,
     player_health: Res<PlayerHealth>,
     shield: Res<ShieldState>,
+    director: Res<WaveDirector>,
+    players: Query<&Player>,
     mut param_set: ParamSet<(
         Query<&mut Text, (With<HudScore>, Without<ShopCostText>, Without<ShopLevelText>)>,
         Query<&mut Text, (With<HudStatus>, Without<HudScore>, Without<ShopCostText>, Without<ShopLevelText>)>,
         Query<&mut Text, (With<HudHealth>, Without<HudScore>, Without<ShopCostText>, Without<ShopLevelText>)>,
         Query<&mut Text, (With<HudBuffs>, Without<ShopCostText>, Without<ShopLevelText>)>,
         Query<&mut Text, (With<HudCombo>, Without<ShopCostText>, Without<ShopLevelText>)>,
+        Query<&mut Text, (With<HudCurrency>, Without<ShopCostText>, Without<ShopLevelText>)>,
+        Query<&mut Text, (With<HudWeapon>, Without<ShopCostText>, Without<ShopLevelText>)>,
+        Query<&mut Text, (With<HudWave>, Without<ShopCostText>, Without<ShopLevelText>)>,
     )>,
 ) {
     if let Ok(mut text) = param_set.p0().get_single_mut() {
@@ -593,8 +719,61 @@ fn update_hud(
     }
 
     if let Ok(mut text) = param_set.p4().get_single_mut() {
+        if combo.is_active() {
+            text.sections[0].value = format!("Combo {}  x{:.1}", combo.count, combo.multiplier);
+        } else {
+            text.sections[0].value = "No Combo".into();
+        }
+        text.sections[0].style.color = combo_color(&theme, combo.count);
+    }
+
+    if let Ok(mut text) = param_set.p5().get_single_mut() {
         text.sections[0].value = format!("Currency {}", currency.balance);
     }
+
+    if let Ok(player) = players.get_single() {
+        if let Ok(mut text) = param_set.p6().get_single_mut() {
+            text.sections[0].value = format!("Weapon {}", player.weapon.display_name());
+        }
+    }
+
+    if let Ok(mut text) = param_set.p7().get_single_mut() {
+        text.sections[0].value = if director.in_lull() {
+            format!("Wave {} in {:.0}s", director.wave + 1, director.lull.ceil())
+        } else {
+            format!("Wave {}", director.wave)
+        };
+    }
+}
+
+fn combo_color(theme: &UiTheme, count: u32) -> Color {
+    match count {
+        0..=1 => theme.text_muted,
+        2..=9 => theme.text_primary,
+        10..=19 => theme.combo_warm,
+        _ => theme.combo_hot,
+    }
+}
+
+fn pulse_combo_on_milestone(
+    time: Res<Time>,
+    mut events: EventReader<ComboMilestoneEvent>,
+    mut pulse: Local<f32>,
+    mut combo_text: Query<&mut Text, With<HudCombo>>,
+) {
+    if events.read().last().is_some() {
+        *pulse = COMBO_PULSE_DURATION;
+    }
+
+    if *pulse <= 0.0 {
+        return;
+    }
+
+    *pulse = (*pulse - time.delta_seconds()).max(0.0);
+    if let Ok(mut text) = combo_text.get_single_mut() {
+        let t = *pulse / COMBO_PULSE_DURATION;
+        text.sections[0].style.font_size = COMBO_FONT_SIZE * (1.0 + 0.4 * t);
+    }
 }
 
 fn update_health_bar(
@@ -614,62 +793,113 @@ fn update_health_bar(
     }
 }
 
-fn update_shop_button_label(
-    run_state: Res<RunState>,
-    shop_state: Res<ShopState>,
-    mut buttons: Query<(&mut Text, &mut BackgroundColor), (With<ShopButton>, Without<ShopModal>)>,
-    theme: Res<UiTheme>,
+fn update_boss_bar(
+    bosses: Query<(&Boss, &EnemyHealth)>,
+    mut panel: Query<&mut Visibility, With<HudBossBar>>,
+    mut name: Query<&mut Text, With<HudBossName>>,
+    mut fill: Query<&mut Style, With<HudBossFill>>,
 ) {
-    if run_state.is_changed() || shop_state.is_changed() {
-        if let Ok((mut text, mut background)) = buttons.get_single_mut() {
-            text.sections[0].value = if shop_state.is_open {
-                "Close Shop".into()
-            } else {
-                "Open Shop".into()
-            };
-            background.0 = if shop_state.is_open {
-                theme.accent.into()
-            } else {
-                theme.accent_soft.into()
-            };
+    let Ok(mut visibility) = panel.get_single_mut() else {
+        return;
+    };
+    let Some((boss, health)) = bosses.iter().next() else {
+        if *visibility != Visibility::Hidden {
+            *visibility = Visibility::Hidden;
+        }
+        return;
+    };
+
+    *visibility = Visibility::Inherited;
+    if let Ok(mut text) = name.get_single_mut() {
+        if text.sections[0].value != boss.name {
+            text.sections[0].value.clone_from(&boss.name);
         }
     }
+    if let Ok(mut style) = fill.get_single_mut() {
+        let percent = if boss.max_health <= 0.0 {
+            0.0
+        } else {
+            health.current / boss.max_health * 100.0
+        };
+        style.width = Val::Percent(percent.clamp(0.0, 100.0));
+    }
 }
 
-fn sync_shop_visibility(
-    shop_state: Res<ShopState>,
+fn show_shop_button(mut buttons: Query<&mut Visibility, With<ShopButton>>) {
+    if let Ok(mut visibility) = buttons.get_single_mut() {
+        *visibility = Visibility::Inherited;
+    }
+}
+
+fn hide_shop_button(mut buttons: Query<&mut Visibility, With<ShopButton>>) {
+    if let Ok(mut visibility) = buttons.get_single_mut() {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn open_shop_overlay(
+    mut shop_state: ResMut<ShopState>,
+    theme: Res<UiTheme>,
     mut overlay: Query<&mut Visibility, With<ShopRoot>>,
+    mut buttons: Query<(&mut BackgroundColor, &Children), With<ShopButton>>,
+    mut texts: Query<&mut Text>,
 ) {
-    if !shop_state.is_changed() {
-        return;
+    shop_state.selected_index = 0;
+    if let Ok(mut visibility) = overlay.get_single_mut() {
+        *visibility = Visibility::Visible;
     }
+    set_shop_button_label(&mut buttons, &mut texts, "Close Shop", theme.accent);
+}
+
+fn close_shop_overlay(
+    theme: Res<UiTheme>,
+    mut overlay: Query<&mut Visibility, With<ShopRoot>>,
+    mut buttons: Query<(&mut BackgroundColor, &Children), With<ShopButton>>,
+    mut texts: Query<&mut Text>,
+) {
     if let Ok(mut visibility) = overlay.get_single_mut() {
-        *visibility = if shop_state.is_open {
-            Visibility::Visible
-        } else {
-            Visibility::Hidden
-        };
+        *visibility = Visibility::Hidden;
+    }
+    set_shop_button_label(&mut buttons, &mut texts, "Open Shop", theme.accent_soft);
+}
+
+fn set_shop_button_label(
+    buttons: &mut Query<(&mut BackgroundColor, &Children), With<ShopButton>>,
+    texts: &mut Query<&mut Text>,
+    label: &str,
+    color: Color,
+) {
+    let Ok((mut background, children)) = buttons.get_single_mut() else {
+        return;
+    };
+    background.0 = color;
+    for child in children.iter() {
+        if let Ok(mut text) = texts.get_mut(*child) {
+            text.sections[0].value = label.into();
+        }
     }
 }
 
 fn handle_shop_open_close(
     mut interactions: Query<&Interaction, (Changed<Interaction>, With<ShopButton>)>,
-    mut shop_state: ResMut<ShopState>,
-    mut run_state: ResMut<RunState>,
+    state: Option<Res<State<GameState>>>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
+    let Some(state) = state else {
+        return;
+    };
+
     for interaction in &mut interactions {
         if *interaction == Interaction::Pressed {
-            if shop_state.is_open {
-                run_state.resume();
-            } else {
-                run_state.pause();
-                shop_state.selected_index = 0;
-            }
-            shop_state.toggle();
+            next_state.set(match state.get() {
+                GameState::Shop => GameState::Running,
+                GameState::Running | GameState::Paused => GameState::Shop,
+            });
         }
     }
 }
 
+#[allow(clippy::type_complexity)]
 fn handle_shop_purchases(
     mut interactions: Query<(&Interaction, &ShopPurchaseButton), Changed<Interaction>>,
     mut shop_state: ResMut<ShopState>,
@@ -745,33 +975,30 @@ fn handle_shop_purchases(
 fn handle_keyboard_navigation(
     keys: Res<ButtonInput<KeyCode>>,
     mut shop_state: ResMut<ShopState>,
-    mut run_state: ResMut<RunState>,
+    mut next_state: ResMut<NextState<GameState>>,
     mut writer: EventWriter<ShopPurchaseEvent>,
 ) {
-    if shop_state.is_open {
-        if keys.just_pressed(KeyCode::ArrowRight) {
-            shop_state.selected_index = (shop_state.selected_index + 1) % SHOP_ITEMS.len();
-        }
-        if keys.just_pressed(KeyCode::ArrowLeft) {
-            shop_state.selected_index = shop_state
-                .selected_index
-                .checked_sub(1)
-                .unwrap_or(SHOP_ITEMS.len() - 1);
-        }
-        if keys.just_pressed(KeyCode::ArrowDown) {
-            shop_state.selected_index = (shop_state.selected_index + 2).min(SHOP_ITEMS.len() - 1);
-        }
-        if keys.just_pressed(KeyCode::ArrowUp) {
-            shop_state.selected_index = shop_state.selected_index.saturating_sub(2);
-        }
+    if keys.just_pressed(KeyCode::ArrowRight) {
+        shop_state.selected_index = (shop_state.selected_index + 1) % SHOP_ITEMS.len();
+    }
+    if keys.just_pressed(KeyCode::ArrowLeft) {
+        shop_state.selected_index = shop_state
+            .selected_index
+            .checked_sub(1)
+            .unwrap_or(SHOP_ITEMS.len() - 1);
+    }
+    if keys.just_pressed(KeyCode::ArrowDown) {
+        shop_state.selected_index = (shop_state.selected_index + 2).min(SHOP_ITEMS.len() - 1);
+    }
+    if keys.just_pressed(KeyCode::ArrowUp) {
+        shop_state.selected_index = shop_state.selected_index.saturating_sub(2);
     }
 
-    if keys.just_pressed(KeyCode::Escape) && shop_state.is_open {
-        shop_state.is_open = false;
-        run_state.resume();
+    if keys.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::Running);
     }
 
-    if keys.just_pressed(KeyCode::Enter) && shop_state.is_open {
+    if keys.just_pressed(KeyCode::Enter) {
         let index = shop_state.selected_index.min(SHOP_ITEMS.len() - 1);
         writer.send(ShopPurchaseEvent {
             item: SHOP_ITEMS[index].upgrade,
@@ -779,33 +1006,57 @@ fn handle_keyboard_navigation(
     }
 }
 
+fn handle_pause_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    state: Option<Res<State<GameState>>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let Some(state) = state else {
+        return;
+    };
+    if !keys.just_pressed(KeyCode::Escape) {
+        return;
+    }
+
+    match state.get() {
+        GameState::Running => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Running),
+        GameState::Shop => {}
+    }
+}
+
 fn handle_gamepad_navigation(
     mut events: EventReader<GamepadEvent>,
     mut shop_state: ResMut<ShopState>,
-    mut run_state: ResMut<RunState>,
+    state: Option<Res<State<GameState>>>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
+    let Some(state) = state else {
+        events.clear();
+        return;
+    };
+
     for event in events.read() {
         if let GamepadEvent::Button(button_event) = event {
             if button_event.value <= 0.5 {
                 continue;
             }
 
-            match button_event.button_type {
-                GamepadButtonType::South => {
-                    if shop_state.is_open {
-                        shop_state.selected_index =
-                            (shop_state.selected_index + 1) % SHOP_ITEMS.len();
-                    }
+            match (button_event.button_type, state.get()) {
+                (GamepadButtonType::South, GameState::Shop) => {
+                    shop_state.selected_index = (shop_state.selected_index + 1) % SHOP_ITEMS.len();
                 }
-                GamepadButtonType::East => {
-                    if shop_state.is_open {
-                        shop_state.is_open = false;
-                        run_state.resume();
-                    } else {
-                        shop_state.is_open = true;
-                        run_state.pause();
-                        shop_state.selected_index = 0;
-                    }
+                (GamepadButtonType::East, GameState::Shop) => {
+                    next_state.set(GameState::Running);
+                }
+                (GamepadButtonType::East, GameState::Running | GameState::Paused) => {
+                    next_state.set(GameState::Shop);
+                }
+                (GamepadButtonType::Start, GameState::Running) => {
+                    next_state.set(GameState::Paused);
+                }
+                (GamepadButtonType::Start, GameState::Paused) => {
+                    next_state.set(GameState::Running);
                 }
                 _ => {}
             }
@@ -813,6 +1064,247 @@ fn handle_gamepad_navigation(
     }
 }
 
+fn handle_start_input(
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    touches: Res<Touches>,
+    mut gamepad_events: EventReader<GamepadEvent>,
+    mut next_state: ResMut<NextState<AppState>>,
+) {
+    let mut pressed = keys.any_just_pressed([KeyCode::Enter, KeyCode::Space])
+        || mouse.just_pressed(MouseButton::Left)
+        || touches.any_just_pressed();
+    for event in gamepad_events.read() {
+        if let GamepadEvent::Button(button_event) = event {
+            if button_event.button_type == GamepadButtonType::South && button_event.value > 0.5 {
+                pressed = true;
+            }
+        }
+    }
+
+    if pressed {
+        next_state.set(AppState::InGame);
+    }
+}
+
+fn spawn_title_overlay(mut commands: Commands, theme: Res<UiTheme>, assets: Res<GameAssets>) {
+    spawn_overlay(
+        &mut commands,
+        &theme,
+        &assets.font_primary,
+        "Threadweaver",
+        &["Click, tap or press Enter to start weaving"],
+        (TitleOverlay, StateScoped(AppState::Title)),
+        |_| {},
+    );
+}
+
+fn spawn_pause_overlay(mut commands: Commands, theme: Res<UiTheme>, assets: Res<GameAssets>) {
+    spawn_overlay(
+        &mut commands,
+        &theme,
+        &assets.font_primary,
+        "Paused",
+        &["Press Esc or Start to resume"],
+        (PauseOverlay, StateScoped(GameState::Paused)),
+        |_| {},
+    );
+}
+
+fn spawn_game_over_overlay(
+    mut commands: Commands,
+    theme: Res<UiTheme>,
+    assets: Res<GameAssets>,
+    summary: Res<RunSummary>,
+) {
+    let font = assets.font_primary.clone();
+    let stats = &summary.stats;
+    let best = &summary.previous_best;
+    let rows = [
+        (
+            "Score",
+            format!("{:04}", summary.score),
+            format!("{:04}", best.score),
+        ),
+        (
+            "Time survived",
+            format_duration(stats.time_survived),
+            format_duration(best.stats.time_survived),
+        ),
+        (
+            "Enemies defeated",
+            format!(
+                "{} ({} trail / {} wave / {} contact)",
+                stats.total_kills(),
+                stats.trail_kills,
+                stats.wave_kills,
+                stats.contact_kills
+            ),
+            best.stats.total_kills().to_string(),
+        ),
+        (
+            "Damage taken",
+            stats.damage_taken.to_string(),
+            best.stats.damage_taken.to_string(),
+        ),
+        (
+            "Pickups",
+            stats.pickups.to_string(),
+            best.stats.pickups.to_string(),
+        ),
+        (
+            "Currency earned",
+            stats.currency_earned.to_string(),
+            best.stats.currency_earned.to_string(),
+        ),
+        (
+            "Peak combo",
+            stats.peak_combo.to_string(),
+            best.stats.peak_combo.to_string(),
+        ),
+    ];
+
+    let heading = match summary.replay {
+        Some(_) => "Replay Finished",
+        None if summary.new_best => "New Best Run!",
+        None => "The Thread Snapped",
+    };
+    let seed = match summary.replay {
+        Some(check) if check.diverged() => format!(
+            "Seed {} - diverged from the recorded score of {:04}",
+            summary.seed, check.recorded
+        ),
+        Some(_) => format!("Seed {} - matched the recorded score", summary.seed),
+        None => format!("Seed {}", summary.seed),
+    };
+
+    spawn_overlay(
+        &mut commands,
+        &theme,
+        &font,
+        heading,
+        &[&seed, "Click, tap or press Enter to weave again"],
+        (GameOverOverlay, StateScoped(AppState::GameOver)),
+        |panel| {
+            spawn_summary_row(panel, &theme, &font, "", "This run", "Best");
+            for (label, value, best) in &rows {
+                spawn_summary_row(panel, &theme, &font, label, value, best);
+            }
+        },
+    );
+}
+
+fn spawn_summary_row(
+    parent: &mut ChildBuilder,
+    theme: &UiTheme,
+    font: &Handle<Font>,
+    label: &str,
+    value: &str,
+    best: &str,
+) {
+    let cell = |text: &str, color: Color, width: f32| TextBundle {
+        style: Style {
+            width: Val::Px(width),
+            ..Default::default()
+        },
+        ..TextBundle::from_section(
+            text,
+            TextStyle {
+                font: font.clone(),
+                font_size: 18.0,
+                color,
+            },
+        )
+    };
+
+    parent
+        .spawn(NodeBundle {
+            style: Style {
+                width: Val::Percent(100.0),
+                column_gap: Val::Px(16.0),
+                justify_content: JustifyContent::SpaceBetween,
+                ..Default::default()
+            },
+            ..Default::default()
+        })
+        .with_children(|row| {
+            row.spawn(cell(label, theme.text_muted, 160.0));
+            row.spawn(cell(value, theme.text_primary, 200.0));
+            row.spawn(cell(best, theme.text_muted, 80.0));
+        });
+}
+
+fn format_duration(seconds: f32) -> String {
+    let total = seconds.max(0.0) as u32;
+    format!("{}:{:02}", total / 60, total % 60)
+}
+
+fn spawn_overlay(
+    commands: &mut Commands,
+    theme: &UiTheme,
+    font: &Handle<Font>,
+    heading: &str,
+    lines: &[&str],
+    marker: impl Bundle,
+    body: impl FnOnce(&mut ChildBuilder),
+) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..Default::default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.55).into(),
+                z_index: ZIndex::Global(10),
+                ..Default::default()
+            },
+            marker,
+        ))
+        .with_children(|overlay| {
+            overlay
+                .spawn(NodeBundle {
+                    style: Style {
+                        min_width: Val::Px(320.0),
+                        padding: UiRect::all(Val::Px(28.0)),
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        row_gap: Val::Px(14.0),
+                        ..Default::default()
+                    },
+                    background_color: theme.panel_background.into(),
+                    border_color: theme.panel_border.into(),
+                    border_radius: BorderRadius::all(Val::Px(18.0)),
+                    ..Default::default()
+                })
+                .with_children(|panel| {
+                    panel.spawn(TextBundle::from_section(
+                        heading,
+                        TextStyle {
+                            font: font.clone(),
+                            font_size: 36.0,
+                            color: theme.text_primary,
+                        },
+                    ));
+                    body(panel);
+                    for line in lines {
+                        panel.spawn(TextBundle::from_section(
+                            *line,
+                            TextStyle {
+                                font: font.clone(),
+                                font_size: 18.0,
+                                color: theme.text_muted,
+                            },
+                        ));
+                    }
+                });
+        });
+}
+
 fn highlight_selected_card(
     shop_state: Res<ShopState>,
     mut cards: Query<(&ShopCard, &mut BorderColor, &mut BackgroundColor)>,